@@ -0,0 +1,59 @@
+// This example demonstrates multi-pass rendering using a separable Gaussian blur.
+// The horizontal pass blurs the source into a texture of half the resolution,
+// the vertical pass blurs that texture, and the final pass blends the result with the source.
+#pragma shaderfilter pass horizontal blur_horizontal 0.5 rgba16f
+#pragma shaderfilter pass vertical blur_vertical 0.5 rgba16f
+uniform int2 builtin_uv_size_horizontal;
+
+#pragma shaderfilter set radius__description Blur Radius
+#pragma shaderfilter set radius__default 4.0
+#pragma shaderfilter set radius__min 0.0
+#pragma shaderfilter set radius__max 16.0
+uniform float radius;
+
+#pragma shaderfilter set strength__description Blur Strength
+#pragma shaderfilter set strength__default 1.0
+#pragma shaderfilter set strength__min 0.0
+#pragma shaderfilter set strength__max 1.0
+uniform float strength;
+
+// The weight of the texel at the given offset, following a Gaussian of `radius` texels
+float gaussian(int offset) {
+    float sigma = max(radius, 0.001) / 2.0;
+
+    return exp(-float(offset * offset) / (2.0 * sigma * sigma));
+}
+
+float4 blur_horizontal(float2 uv) {
+    float2 texel = float2(1.0 / float(builtin_uv_size.x), 0.0);
+    float4 color = float4(0.0, 0.0, 0.0, 0.0);
+    float weight_sum = 0.0;
+
+    for (int i = -16; i <= 16; i++) {
+        color += gaussian(i) * image.Sample(builtin_texture_sampler, uv + float(i) * texel);
+        weight_sum += gaussian(i);
+    }
+
+    return color / weight_sum;
+}
+
+float4 blur_vertical(float2 uv) {
+    // The offsets are in texels of the source, which has twice the resolution of the horizontal pass
+    float2 texel = float2(0.0, 0.5 / float(builtin_uv_size_horizontal.y));
+    float4 color = float4(0.0, 0.0, 0.0, 0.0);
+    float weight_sum = 0.0;
+
+    for (int i = -16; i <= 16; i++) {
+        color += gaussian(i) * horizontal.Sample(builtin_texture_sampler, uv + float(i) * texel);
+        weight_sum += gaussian(i);
+    }
+
+    return color / weight_sum;
+}
+
+float4 render(float2 uv) {
+    float4 image_color = image.Sample(builtin_texture_sampler, uv);
+    float4 blurred_color = vertical.Sample(builtin_texture_sampler, uv);
+
+    return lerp(image_color, blurred_color, strength);
+}
@@ -1,5 +1,6 @@
 uniform float4x4 ViewProj;
 uniform texture2d image;
+__PASS_DECLARATIONS__
 
 uniform int   builtin_frame;
 uniform float builtin_framerate;
@@ -10,6 +11,7 @@ uniform float builtin_elapsed_time_since_shown_previous;
 uniform float builtin_elapsed_time_since_enabled;
 uniform float builtin_elapsed_time_since_enabled_previous;
 uniform int2  builtin_uv_size;
+uniform float4 builtin_date;
 
 sampler_state builtin_texture_sampler {
     Filter = Linear;
@@ -34,6 +36,7 @@ BuiltinVertData builtin_shader_vertex(BuiltinVertData v_in)
 }
 
 float4 builtin_shader_fragment(BuiltinVertData v_in) : TARGET {
+    __PASS_DISPATCH__
     return render(v_in.uv);
 }
 
@@ -0,0 +1,67 @@
+uniform float4x4 ViewProj;
+uniform texture2d image;
+
+uniform int   builtin_frame;
+uniform float builtin_framerate;
+uniform float builtin_elapsed_time;
+uniform float builtin_elapsed_time_previous;
+uniform float builtin_elapsed_time_since_shown;
+uniform float builtin_elapsed_time_since_shown_previous;
+uniform float builtin_elapsed_time_since_enabled;
+uniform float builtin_elapsed_time_since_enabled_previous;
+uniform int2  builtin_uv_size;
+uniform float4 builtin_date;
+
+sampler_state builtin_texture_sampler {
+    Filter = Linear;
+    AddressU = Border;
+    AddressV = Border;
+    BorderColor = 00000000;
+};
+
+__SHADERTOY_CHANNELS__
+
+struct BuiltinVertData {
+    float4 pos : POSITION;
+    float2 uv : TEXCOORD0;
+};
+
+// GLSL `mod` differs from `fmod` for negative numbers
+float  builtin_mod(float  x, float  y) { return x - y * floor(x / y); }
+float2 builtin_mod(float2 x, float2 y) { return x - y * floor(x / y); }
+float3 builtin_mod(float3 x, float3 y) { return x - y * floor(x / y); }
+float4 builtin_mod(float4 x, float4 y) { return x - y * floor(x / y); }
+float2 builtin_mod(float2 x, float  y) { return x - y * floor(x / y); }
+float3 builtin_mod(float3 x, float  y) { return x - y * floor(x / y); }
+float4 builtin_mod(float4 x, float  y) { return x - y * floor(x / y); }
+
+// Shadertoy places the origin in the bottom left corner
+float2 builtin_shadertoy_flip(float2 uv) {
+    return float2(uv.x, 1.0 - uv.y);
+}
+
+__SHADER__
+
+BuiltinVertData builtin_shader_vertex(BuiltinVertData v_in)
+{
+    BuiltinVertData vert_out;
+    vert_out.pos = mul(float4(v_in.pos.xyz, 1.0), ViewProj);
+    vert_out.uv = v_in.uv;
+    return vert_out;
+}
+
+float4 builtin_shader_fragment(BuiltinVertData v_in) : TARGET {
+    float4 color = float4(0.0, 0.0, 0.0, 1.0);
+    mainImage(color, builtin_shadertoy_flip(v_in.uv) * float2(builtin_uv_size));
+    // Shadertoy ignores the alpha channel
+    return float4(color.rgb, 1.0);
+}
+
+technique Draw
+{
+    pass
+    {
+        vertex_shader = builtin_shader_vertex(v_in);
+        pixel_shader = builtin_shader_fragment(v_in);
+    }
+}
@@ -0,0 +1,224 @@
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use hound::{SampleFormat, WavReader};
+use super::{AudioInput, AudioInputCallback};
+
+/// How often the samples, which would have been played in the meantime, are delivered
+const PLAYBACK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The samples of a single channel of a WAV file, which are read in a loop.
+pub struct WavLoop {
+    samples: Vec<f32>,
+    sample_rate: f64,
+    /// The index of the next sample to read
+    position: usize,
+}
+
+impl WavLoop {
+    pub fn open(path: &Path, channel: usize) -> Result<Self, Cow<'static, str>> {
+        let reader = WavReader::open(path)
+            .map_err(|error| format!("Could not open the audio file: {}", error))?;
+
+        Self::from_reader(reader, channel)
+    }
+
+    /// Decodes the samples of the given channel, integer samples are normalized to the range [-1; 1].
+    pub fn from_reader<R: Read>(reader: WavReader<R>, channel: usize) -> Result<Self, Cow<'static, str>> {
+        let spec = reader.spec();
+
+        if channel >= spec.channels as usize {
+            throw!(format!("The audio file has only {} channel(s).", spec.channels));
+        }
+
+        let interleaved_samples = match spec.sample_format {
+            SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+            SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+
+                reader.into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        }.map_err(|error| format!("Could not decode the audio file: {}", error))?;
+        let samples = interleaved_samples.into_iter()
+            .skip(channel)
+            .step_by(spec.channels as usize)
+            .collect::<Vec<_>>();
+
+        if samples.is_empty() {
+            throw!("The audio file does not contain any samples.");
+        }
+
+        Ok(Self {
+            samples,
+            sample_rate: spec.sample_rate as f64,
+            position: 0,
+        })
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Returns the next `len` samples, continuing from the start when the end of the file is reached.
+    pub fn next_chunk(&mut self, len: usize) -> Vec<f32> {
+        let mut chunk = Vec::with_capacity(len);
+
+        while chunk.len() < len {
+            let available = (len - chunk.len()).min(self.samples.len() - self.position);
+
+            chunk.extend_from_slice(&self.samples[self.position..(self.position + available)]);
+            self.position = (self.position + available) % self.samples.len();
+        }
+
+        chunk
+    }
+}
+
+/// Plays a WAV file in a loop at real-time speed on a separate thread,
+/// so that audio-reactive shaders can be designed without any audio playing through OBS.
+pub struct WavFileAudioInput {
+    stopped: Arc<AtomicBool>,
+    /// `None`, if the file could not be loaded
+    thread: Option<JoinHandle<()>>,
+    /// Why the file could not be loaded
+    error: Option<String>,
+}
+
+impl WavFileAudioInput {
+    pub fn new(path: &Path, channel: usize, callback: AudioInputCallback) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let (thread, error) = match WavLoop::open(path, channel) {
+            Ok(wav_loop) => {
+                let stopped = stopped.clone();
+
+                (Some(thread::spawn(move || Self::play(wav_loop, &stopped, callback))), None)
+            },
+            Err(error) => {
+                (None, Some(format!("Could not play the audio file `{}`: {}", path.display(), error)))
+            },
+        };
+
+        Self {
+            stopped,
+            thread,
+            error,
+        }
+    }
+
+    /// Delivers the samples at the rate they would be played at, until stopped.
+    fn play(mut wav_loop: WavLoop, stopped: &AtomicBool, callback: AudioInputCallback) {
+        let start = Instant::now();
+        let mut samples_played = 0;
+
+        while !stopped.load(Ordering::SeqCst) {
+            let samples_due = (start.elapsed().as_secs_f64() * wav_loop.sample_rate()) as usize;
+
+            if samples_due > samples_played {
+                callback(&wav_loop.next_chunk(samples_due - samples_played), wav_loop.sample_rate());
+                samples_played = samples_due;
+            }
+
+            thread::sleep(PLAYBACK_INTERVAL);
+        }
+    }
+}
+
+impl AudioInput for WavFileAudioInput {
+    fn error(&self) -> Option<Cow<str>> {
+        self.error.as_ref().map(|error| Cow::Borrowed(error.as_str()))
+    }
+}
+
+impl Drop for WavFileAudioInput {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use hound::{WavSpec, WavWriter};
+    use crate::spectrum::SpectrumAnalyzer;
+    use crate::test_util::{analyzer_parameters, peak_index, sine, SAMPLE_RATE};
+
+    fn encode_wav(spec: WavSpec, write_samples: impl FnOnce(&mut WavWriter<Cursor<&mut Vec<u8>>>)) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut writer = WavWriter::new(Cursor::new(&mut bytes), spec).unwrap();
+
+        write_samples(&mut writer);
+        writer.finalize().unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn test_channel_selection_and_looping() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let bytes = encode_wav(spec, |writer| {
+            for index in 0..4 {
+                writer.write_sample(index as i16 * 1024).unwrap();
+                writer.write_sample(-16384i16).unwrap();
+            }
+        });
+        let mut left = WavLoop::from_reader(WavReader::new(Cursor::new(&bytes)).unwrap(), 0).unwrap();
+        let mut right = WavLoop::from_reader(WavReader::new(Cursor::new(&bytes)).unwrap(), 1).unwrap();
+
+        assert_eq!(left.sample_rate(), 8000.0);
+        assert_eq!(left.next_chunk(3), vec![0.0, 0.03125, 0.0625]);
+        assert_eq!(left.next_chunk(3), vec![0.09375, 0.0, 0.03125]);
+        assert_eq!(right.next_chunk(6), vec![-0.5; 6]);
+        assert!(WavLoop::from_reader(WavReader::new(Cursor::new(&bytes)).unwrap(), 2).is_err());
+    }
+
+    #[test]
+    fn test_analysis_of_wav_file() {
+        const FFT_SIZE: usize = 1024;
+
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE as u32,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        // A sine wave with the frequency of bin 32, with a whole number of periods, so that it loops seamlessly
+        let frequency = 32.0 * SAMPLE_RATE / FFT_SIZE as f64;
+        let bytes = encode_wav(spec, |writer| {
+            for sample in sine(frequency, 0.5, FFT_SIZE * 4) {
+                writer.write_sample(sample).unwrap();
+            }
+        });
+        let mut wav_loop = WavLoop::from_reader(WavReader::new(Cursor::new(&bytes)).unwrap(), 0).unwrap();
+        let mut analyzer = SpectrumAnalyzer::new(analyzer_parameters(FFT_SIZE, FFT_SIZE / 2), wav_loop.sample_rate());
+        let mut analyses = 0;
+
+        // Deliver the samples in chunks of a 60 FPS video frame, across the end of the file
+        for _ in 0..20 {
+            analyzer.push_samples(&wav_loop.next_chunk(800));
+
+            if analyzer.analyze() {
+                assert_eq!(peak_index(&analyzer.result().unwrap().magnitudes), 32);
+                analyses += 1;
+            }
+        }
+
+        assert_eq!(analyzer.result().unwrap().batch_number + 1, analyses);
+        assert_eq!(analyses, 19);
+    }
+}
@@ -0,0 +1,76 @@
+use std::borrow::Cow;
+use std::path::PathBuf;
+use obs_wrapper::{audio::*, info::*};
+
+mod source;
+mod file;
+
+// Re-export the public API
+pub use source::SourceAudioInput;
+pub use file::{WavFileAudioInput, WavLoop};
+
+/// Called with the normalized samples of the captured channel, along with their sample rate.
+pub type AudioInputCallback = Box<dyn Fn(&[f32], f64) + Send + Sync>;
+
+/// Where the analysed audio comes from.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum AudioInputDescriptor {
+    /// The index of an output mix/track
+    Mix(usize),
+    /// The name of a source, whose audio is captured before it is mixed
+    Source(String),
+    /// The path to a WAV file, which is played in a loop at real-time speed
+    File(PathBuf),
+}
+
+impl AudioInputDescriptor {
+    /// Starts delivering the samples of the given channel to the callback.
+    pub fn connect(&self, channel: usize, callback: AudioInputCallback) -> Box<dyn AudioInput> {
+        match self {
+            AudioInputDescriptor::Mix(mix) => Box::new(MixAudioInput::new(*mix, channel, callback)),
+            AudioInputDescriptor::Source(name) => Box::new(SourceAudioInput::new(name, channel, callback)),
+            AudioInputDescriptor::File(path) => Box::new(WavFileAudioInput::new(path, channel, callback)),
+        }
+    }
+}
+
+/// A connection to a stream of audio samples, which is disconnected when dropped.
+/// The audio analysis does not depend on where the samples come from.
+pub trait AudioInput: Send {
+    /// Must be called periodically, so that inputs may reconnect to their source.
+    fn update(&self) {}
+
+    /// A problem preventing samples from being delivered, to be displayed to the user, if there is one.
+    fn error(&self) -> Option<Cow<str>> {
+        None
+    }
+}
+
+/// Captures the audio of an output mix/track.
+pub struct MixAudioInput {
+    /// Disconnected from the mix when dropped
+    #[allow(dead_code)]
+    output: AudioOutput,
+}
+
+impl MixAudioInput {
+    pub fn new(mix: usize, channel: usize, callback: AudioInputCallback) -> Self {
+        let sample_rate = ObsAudioInfo::get()
+            .expect("Audio info not accessible.")
+            .samples_per_second() as f64;
+        let audio = Audio::get();
+
+        Self {
+            output: audio.connect_output(
+                mix,
+                Box::new(move |audio_data| {
+                    if let Some(samples) = audio_data.samples_normalized(channel) {
+                        callback(samples, sample_rate);
+                    }
+                }),
+            ),
+        }
+    }
+}
+
+impl AudioInput for MixAudioInput {}
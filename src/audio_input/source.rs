@@ -0,0 +1,158 @@
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::sync::Mutex;
+use obs_wrapper::{obs_sys::*, info::*};
+use crate::effect::SourceReference;
+use super::{AudioInput, AudioInputCallback};
+
+/// Muted sources are analysed as silence, which is delivered in chunks of at most this many samples,
+/// so that no buffer has to be allocated on the audio thread.
+static SILENCE: [f32; 1024] = [0.0; 1024];
+
+struct SourceAudioInputShared {
+    channel: usize,
+    sample_rate: f64,
+    callback: AudioInputCallback,
+}
+
+struct SourceAudioInputState {
+    /// Follows the source when it is renamed and looks it up again, when it is recreated
+    reference: SourceReference,
+    /// The source the callback is currently registered with
+    connected: *mut obs_weak_source_t,
+}
+
+// Weak references may be used from any thread
+unsafe impl Send for SourceAudioInputState {}
+
+impl SourceAudioInputState {
+    /// Returns a strong reference to the connected source, or a null pointer.
+    fn connected_source(&self) -> *mut obs_source_t {
+        if self.connected.is_null() {
+            std::ptr::null_mut()
+        } else {
+            unsafe { obs_weak_source_get_source(self.connected) }
+        }
+    }
+
+    fn disconnect(&mut self, shared: *mut c_void) {
+        let connected_source = self.connected_source();
+
+        unsafe {
+            if !connected_source.is_null() {
+                obs_source_remove_audio_capture_callback(connected_source, Some(capture_source_audio), shared);
+                obs_source_release(connected_source);
+            }
+
+            if !self.connected.is_null() {
+                obs_weak_source_release(self.connected);
+                self.connected = std::ptr::null_mut();
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn capture_source_audio(
+    param: *mut c_void,
+    _source: *mut obs_source_t,
+    audio_data: *const audio_data,
+    muted: bool,
+) {
+    let shared = &*(param as *const SourceAudioInputShared);
+    let audio_data = &*audio_data;
+    let frames = audio_data.frames as usize;
+    let data = if let Some(data) = audio_data.data.get(shared.channel).filter(|data| !data.is_null()) {
+        *data
+    } else {
+        // The source does not have the requested channel
+        return;
+    };
+
+    if muted {
+        let mut remaining = frames;
+
+        while remaining > 0 {
+            let len = remaining.min(SILENCE.len());
+
+            (shared.callback)(&SILENCE[..len], shared.sample_rate);
+            remaining -= len;
+        }
+    } else {
+        // Audio capture callbacks receive the audio in the planar float format of the output
+        (shared.callback)(std::slice::from_raw_parts(data as *const f32, frames), shared.sample_rate);
+    }
+}
+
+/// Captures the audio of a single source, before it is mixed.
+pub struct SourceAudioInput {
+    state: Mutex<SourceAudioInputState>,
+    /// Passed to the capture callback, the heap allocation keeps its address stable
+    shared: Box<SourceAudioInputShared>,
+}
+
+impl SourceAudioInput {
+    pub fn new(name: &str, channel: usize, callback: AudioInputCallback) -> Self {
+        let result = Self {
+            state: Mutex::new(SourceAudioInputState {
+                reference: SourceReference::new(CString::new(name).unwrap_or_default()),
+                connected: std::ptr::null_mut(),
+            }),
+            shared: Box::new(SourceAudioInputShared {
+                channel,
+                sample_rate: ObsAudioInfo::get()
+                    .expect("Audio info not accessible.")
+                    .samples_per_second() as f64,
+                callback,
+            }),
+        };
+
+        result.update();
+        result
+    }
+
+    fn shared_ptr(&self) -> *mut c_void {
+        &*self.shared as *const SourceAudioInputShared as *mut c_void
+    }
+}
+
+impl AudioInput for SourceAudioInput {
+    /// Reconnects to the captured source, if it was removed or recreated.
+    fn update(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        state.reference.update();
+
+        let source = state.reference.get();
+        let connected_source = state.connected_source();
+
+        if source != connected_source {
+            state.disconnect(self.shared_ptr());
+
+            if !source.is_null() {
+                unsafe {
+                    obs_source_add_audio_capture_callback(source, Some(capture_source_audio), self.shared_ptr());
+                    state.connected = obs_source_get_weak_source(source);
+                }
+            }
+        }
+
+        unsafe {
+            if !source.is_null() {
+                obs_source_release(source);
+            }
+
+            if !connected_source.is_null() {
+                obs_source_release(connected_source);
+            }
+        }
+    }
+}
+
+impl Drop for SourceAudioInput {
+    fn drop(&mut self) {
+        let shared = self.shared_ptr();
+
+        // After the callback is removed, it is guaranteed not to be running anymore
+        self.state.lock().unwrap().disconnect(shared);
+    }
+}
@@ -0,0 +1,163 @@
+mod onset;
+mod tempo;
+
+use std::collections::VecDeque;
+
+// Re-export the public API
+pub use onset::{OnsetDetector, band_edges, band_energies};
+pub use tempo::{estimate_tempo, fold_interval};
+
+/// The tempo is estimated from the beats within this many seconds before the most recent analysis.
+const TEMPO_WINDOW: f64 = 8.0;
+
+/// Parameters for configuring the beat detection
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeatParameters {
+    /// The number of logarithmically spaced frequency bands the spectral flux is computed in
+    pub bands: usize,
+    pub f_min: f32,
+    pub f_max: f32,
+    pub sensitivity: f32,
+    pub bpm_min: f64,
+    pub bpm_max: f64,
+}
+
+/// The state of the beat detection at a point in time
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BeatState {
+    /// Seconds since the last beat
+    pub time_since_beat: f32,
+    /// The number of beats detected so far
+    pub count: i32,
+    /// The estimated tempo in beats per minute, `0` if not enough beats were detected
+    pub bpm: f32,
+    /// The position within the current beat according to the estimated tempo, from `0` to `1`
+    pub phase: f32,
+    /// The onset strength of the most recent analysis
+    pub strength: f32,
+    /// Seconds since the last onset in each of the frequency bands
+    pub band_times: Vec<f32>,
+}
+
+/// Detects beats in consecutive spectra and estimates the tempo.
+/// Onsets closer together than the shortest beat interval of the tempo range are ignored.
+pub struct BeatDetector {
+    parameters: BeatParameters,
+    onset_detector: OnsetDetector,
+    beat_times: VecDeque<f64>,
+    /// The time of the last onset in each of the frequency bands
+    band_onset_times: Vec<Option<f64>>,
+    count: i32,
+    bpm: Option<f64>,
+}
+
+impl BeatDetector {
+    pub fn new(parameters: BeatParameters) -> Self {
+        Self {
+            onset_detector: OnsetDetector::new(parameters.f_min, parameters.f_max, parameters.bands, parameters.sensitivity),
+            band_onset_times: vec![None; parameters.bands.max(1)],
+            parameters,
+            beat_times: VecDeque::new(),
+            count: 0,
+            bpm: None,
+        }
+    }
+
+    /// Processes the spectrum analysed at `time` in seconds and returns `true`, if a beat was detected.
+    pub fn process(&mut self, spectrum: &[f32], bin_width: f32, time: f64) -> bool {
+        let onset = self.onset_detector.process(spectrum, bin_width, time);
+
+        for (band_onset_time, &band_onset) in self.band_onset_times.iter_mut().zip(self.onset_detector.band_onsets()) {
+            if band_onset {
+                *band_onset_time = Some(time);
+            }
+        }
+
+        if !onset {
+            return false;
+        }
+
+        if let Some(&last_beat) = self.beat_times.back() {
+            if time - last_beat < 60.0 / self.parameters.bpm_max {
+                return false;
+            }
+        }
+
+        self.beat_times.push_back(time);
+        self.count += 1;
+
+        while let Some(&beat_time) = self.beat_times.front() {
+            if time - beat_time > TEMPO_WINDOW {
+                self.beat_times.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let beat_times = self.beat_times.iter().copied().collect::<Vec<_>>();
+
+        if let Some(bpm) = estimate_tempo(&beat_times, self.parameters.bpm_min, self.parameters.bpm_max) {
+            self.bpm = Some(bpm);
+        }
+
+        true
+    }
+
+    /// The state of the beat detection at `time` in seconds, which may lie between analyses.
+    pub fn state(&self, time: f64) -> BeatState {
+        let time_since = |last_time: Option<f64>| {
+            last_time.map(|last_time| (time - last_time).max(0.0))
+                .unwrap_or(time.max(0.0))
+        };
+        let time_since_beat = time_since(self.beat_times.back().copied());
+        let phase = self.bpm
+            .map(|bpm| (time_since_beat * bpm / 60.0).fract())
+            .unwrap_or(0.0);
+
+        BeatState {
+            time_since_beat: time_since_beat as f32,
+            count: self.count,
+            bpm: self.bpm.unwrap_or(0.0) as f32,
+            phase: phase as f32,
+            strength: self.onset_detector.strength(),
+            band_times: self.band_onset_times.iter()
+                .map(|&band_onset_time| time_since(band_onset_time) as f32)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beat_detection() {
+        let mut detector = BeatDetector::new(BeatParameters {
+            bands: 8,
+            f_min: 20.0,
+            f_max: 10000.0,
+            sensitivity: 1.5,
+            bpm_min: 70.0,
+            bpm_max: 180.0,
+        });
+        let quiet = vec![0.01; 256];
+        let loud = vec![0.5; 256];
+
+        // 120 BPM at 60 analyses per second, with an additional onset shortly after each beat
+        for frame in 0..300 {
+            let spectrum = if frame % 30 == 15 || frame % 30 == 18 { &loud } else { &quiet };
+
+            detector.process(spectrum, 40.0, frame as f64 / 60.0);
+        }
+
+        let state = detector.state(285.0 / 60.0 + 0.5);
+
+        assert_eq!(state.count, 10);
+        assert!((state.bpm - 120.0).abs() < 1.0, "{}", state.bpm);
+        assert!((state.time_since_beat - 0.5).abs() < 1e-3);
+        assert!(state.phase.abs() < 1e-3 || (state.phase - 1.0).abs() < 1e-3);
+        assert_eq!(state.band_times.len(), 8);
+        assert!(state.band_times.iter().all(|&band_time| (band_time - state.time_since_beat).abs() < 0.1));
+    }
+}
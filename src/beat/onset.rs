@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+/// Onsets are detected relative to the onset strengths within this many seconds before the current analysis.
+const THRESHOLD_WINDOW: f64 = 1.0;
+/// Onset strengths below this value are never considered onsets, so that noise in silence is ignored.
+const MIN_ONSET_STRENGTH: f32 = 0.01;
+/// Compression factor applied to band energies, `ln(1 + COMPRESSION * energy)`,
+/// so that quiet bands contribute to the onset strength as well.
+const COMPRESSION: f32 = 100.0;
+
+/// Returns `bands + 1` logarithmically spaced band edges in Hz, from `f_min` to `f_max`.
+pub fn band_edges(f_min: f32, f_max: f32, bands: usize) -> Vec<f32> {
+    let f_min = f_min.max(1.0);
+    let f_max = f_max.max(f_min);
+    let ratio = (f_max / f_min).ln();
+
+    (0..=bands)
+        .map(|index| f_min * (ratio * index as f32 / bands.max(1) as f32).exp())
+        .collect()
+}
+
+/// Sums the magnitudes of the spectrum within each band, where `bin_width` is the frequency step between bins in Hz.
+pub fn band_energies(spectrum: &[f32], bin_width: f32, edges: &[f32]) -> Vec<f32> {
+    let mut energies = vec![0.0; edges.len().saturating_sub(1)];
+
+    for (bin, magnitude) in spectrum.iter().enumerate() {
+        let frequency = bin as f32 * bin_width;
+
+        if let Some(band) = edges.windows(2).position(|edge| frequency >= edge[0] && frequency < edge[1]) {
+            energies[band] += magnitude;
+        }
+    }
+
+    energies
+}
+
+/// Detects onsets in a sequence of onset strengths, where the strength exceeds the mean
+/// of the strengths within the last `THRESHOLD_WINDOW` seconds by `sensitivity` standard deviations.
+#[derive(Default)]
+struct AdaptiveThreshold {
+    /// Recent onset strengths along with the time of their analysis
+    history: VecDeque<(f64, f32)>,
+}
+
+impl AdaptiveThreshold {
+    fn process(&mut self, strength: f32, sensitivity: f32, time: f64) -> bool {
+        while let Some(&(history_time, _)) = self.history.front() {
+            if time - history_time > THRESHOLD_WINDOW {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let onset = if self.history.is_empty() {
+            false
+        } else {
+            let len = self.history.len() as f32;
+            let mean = self.history.iter().map(|(_, strength)| strength).sum::<f32>() / len;
+            let variance = self.history.iter().map(|(_, strength)| (strength - mean).powi(2)).sum::<f32>() / len;
+
+            strength >= MIN_ONSET_STRENGTH && strength > mean + sensitivity * variance.sqrt()
+        };
+
+        self.history.push_back((time, strength));
+
+        onset
+    }
+}
+
+/// Detects onsets using the spectral flux, the increase of energy in each band
+/// since the previous analysis. Onsets are detected in each band separately,
+/// as well as in the onset strength, the flux averaged across all bands.
+pub struct OnsetDetector {
+    edges: Vec<f32>,
+    /// Onsets are detected where the onset strength exceeds the mean of the recent strengths
+    /// by `sensitivity` standard deviations.
+    sensitivity: f32,
+    previous_energies: Option<Vec<f32>>,
+    threshold: AdaptiveThreshold,
+    band_thresholds: Vec<AdaptiveThreshold>,
+    strength: f32,
+    band_onsets: Vec<bool>,
+}
+
+impl OnsetDetector {
+    pub fn new(f_min: f32, f_max: f32, bands: usize, sensitivity: f32) -> Self {
+        let bands = bands.max(1);
+
+        Self {
+            edges: band_edges(f_min, f_max, bands),
+            sensitivity,
+            previous_energies: None,
+            threshold: Default::default(),
+            band_thresholds: (0..bands).map(|_| Default::default()).collect(),
+            strength: 0.0,
+            band_onsets: vec![false; bands],
+        }
+    }
+
+    /// The onset strength of the most recent analysis.
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// Whether the most recent analysis contains an onset, for each band.
+    pub fn band_onsets(&self) -> &[bool] {
+        &self.band_onsets
+    }
+
+    /// Processes the spectrum analysed at `time` in seconds and returns `true`, if it contains an onset.
+    pub fn process(&mut self, spectrum: &[f32], bin_width: f32, time: f64) -> bool {
+        let energies = band_energies(spectrum, bin_width, &self.edges).into_iter()
+            .map(|energy| (1.0 + COMPRESSION * energy).ln())
+            .collect::<Vec<_>>();
+        let fluxes = match self.previous_energies.as_ref().filter(|previous| previous.len() == energies.len()) {
+            Some(previous_energies) => {
+                energies.iter()
+                    .zip(previous_energies)
+                    .map(|(current, previous)| (current - previous).max(0.0))
+                    .collect::<Vec<_>>()
+            },
+            None => vec![0.0; energies.len()],
+        };
+        let strength = fluxes.iter().sum::<f32>() / fluxes.len() as f32;
+        let sensitivity = self.sensitivity;
+
+        self.band_onsets = fluxes.iter()
+            .zip(&mut self.band_thresholds)
+            .map(|(&flux, threshold)| threshold.process(flux, sensitivity, time))
+            .collect();
+        self.previous_energies = Some(energies);
+        self.strength = strength;
+
+        self.threshold.process(strength, sensitivity, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_band_edges() {
+        let edges = band_edges(100.0, 1600.0, 4);
+
+        assert_eq!(edges.len(), 5);
+        assert!((edges[0] - 100.0).abs() < 1e-3);
+        assert!((edges[2] - 400.0).abs() < 1e-2);
+        assert!((edges[4] - 1600.0).abs() < 1e-1);
+    }
+
+    #[test]
+    fn test_band_energies() {
+        let spectrum = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let energies = band_energies(&spectrum, 100.0, &[100.0, 250.0, 500.0]);
+
+        assert_eq!(energies, vec![5.0, 9.0]);
+    }
+
+    #[test]
+    fn test_onset_detection() {
+        let mut detector = OnsetDetector::new(20.0, 10000.0, 8, 1.5);
+        let quiet = vec![0.01; 256];
+        let loud = vec![0.5; 256];
+        let onsets = (0..120)
+            .filter(|&frame| {
+                let spectrum = if frame % 30 == 0 { &loud } else { &quiet };
+
+                detector.process(spectrum, 40.0, frame as f64 / 60.0)
+            })
+            .collect::<Vec<_>>();
+
+        // The first frame has no history to compare to
+        assert_eq!(onsets, vec![30, 60, 90]);
+    }
+
+    #[test]
+    fn test_band_onset_detection() {
+        let mut detector = OnsetDetector::new(20.0, 10000.0, 8, 1.5);
+        let quiet = vec![0.01; 256];
+        let mut high = quiet.clone();
+
+        // Only the bins above 5120 Hz, which lie within the last band, get louder
+        high[128..].iter_mut().for_each(|magnitude| *magnitude = 0.5);
+
+        for frame in 0..60 {
+            let spectrum = if frame % 30 == 15 { &high } else { &quiet };
+            let onset = detector.process(spectrum, 40.0, frame as f64 / 60.0);
+
+            if frame == 45 {
+                assert!(onset);
+                assert_eq!(detector.band_onsets(), &[false, false, false, false, false, false, false, true]);
+            }
+        }
+    }
+}
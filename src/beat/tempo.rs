@@ -0,0 +1,72 @@
+/// Intervals within this relative distance of each other are considered to belong to the same tempo.
+const INTERVAL_TOLERANCE: f64 = 0.08;
+
+/// Doubles or halves the interval in seconds, until it corresponds to a tempo between `bpm_min` and `bpm_max`.
+pub fn fold_interval(interval: f64, bpm_min: f64, bpm_max: f64) -> f64 {
+    let interval_min = 60.0 / bpm_max;
+    let interval_max = 60.0 / bpm_min;
+    let mut interval = interval;
+
+    if interval <= 0.0 || interval_min > interval_max {
+        return interval;
+    }
+
+    while interval > interval_max {
+        interval /= 2.0;
+    }
+
+    while interval < interval_min {
+        interval *= 2.0;
+    }
+
+    interval
+}
+
+/// Estimates the tempo in beats per minute from the times of consecutive beats in seconds.
+/// The intervals between beats are folded into the allowed tempo range and the tempo supported
+/// by the most intervals is chosen. At least three beats are required.
+pub fn estimate_tempo(beat_times: &[f64], bpm_min: f64, bpm_max: f64) -> Option<f64> {
+    let intervals = beat_times.windows(2)
+        .map(|times| fold_interval(times[1] - times[0], bpm_min, bpm_max))
+        .filter(|interval| *interval > 0.0)
+        .collect::<Vec<_>>();
+
+    if intervals.len() < 2 {
+        return None;
+    }
+
+    let supporting_intervals = |candidate: f64| {
+        intervals.iter()
+            .copied()
+            .filter(move |interval| (interval - candidate).abs() <= candidate * INTERVAL_TOLERANCE)
+    };
+    let best_candidate = intervals.iter()
+        .copied()
+        .max_by_key(|&candidate| supporting_intervals(candidate).count())?;
+    let (sum, count) = supporting_intervals(best_candidate)
+        .fold((0.0, 0), |(sum, count), interval| (sum + interval, count + 1));
+
+    Some(60.0 * count as f64 / sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_interval() {
+        assert!((fold_interval(1.0, 90.0, 180.0) - 0.5).abs() < 1e-9);
+        assert!((fold_interval(0.25, 90.0, 180.0) - 0.5).abs() < 1e-9);
+        assert!((fold_interval(0.4, 90.0, 180.0) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_tempo() {
+        assert_eq!(estimate_tempo(&[0.0, 0.5], 70.0, 180.0), None);
+
+        // A missed beat and an off-beat onset do not change the estimate
+        let bpm = estimate_tempo(&[0.0, 0.5, 1.0, 2.0, 2.5, 2.8, 3.5], 70.0, 180.0).unwrap();
+
+        assert!((bpm - 120.0).abs() < 1.0, "{}", bpm);
+    }
+}
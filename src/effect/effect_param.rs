@@ -1,9 +1,16 @@
 use std::sync::Arc;
 use std::borrow::Cow;
-use obs_wrapper::{obs_sys::MAX_AUDIO_MIXES, context::*, graphics::*, source::*};
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::ffi::CString;
+use obs_wrapper::{obs_sys::{MAX_AUDIO_MIXES, OBS_SOURCE_AUDIO, OBS_SOURCE_VIDEO, obs_source_release, gs_effect_set_texture}, context::*, graphics::*, source::*};
 use smallvec::{SmallVec, smallvec};
 use paste::item;
 use crate::*;
+use crate::beat::BeatParameters;
+use crate::loudness;
+use crate::mel::MelFormula;
 
 /// Used to convert cloneable values into `ShaderParamType::RustType`.
 pub trait EffectParamType {
@@ -56,6 +63,23 @@ impl Default for TextureDescriptor {
     }
 }
 
+impl TextureDescriptor {
+    /// Decodes an image file into an RGBA texture.
+    pub fn from_image_file(path: &Path) -> Result<Self, Cow<'static, str>> {
+        let image = image::open(path)
+            .map_err(|err| format!("Could not load the image `{}`: {}", path.display(), err))?
+            .into_rgba8();
+        let (width, height) = image.dimensions();
+
+        Ok(Self {
+            dimensions: [width as usize, height as usize],
+            color_format: ColorFormatKind::RGBA,
+            levels: smallvec![image.into_raw()],
+            flags: 0,
+        })
+    }
+}
+
 pub type EffectParamTexture = EffectParam<EffectParamTypeTexture>;
 
 pub struct EffectParamTypeClone<T>
@@ -465,25 +489,562 @@ impl BindableProperty for EffectParamCustomColor {
     }
 }
 
-pub struct EffectParamCustomFFT {
+const VECTOR_COMPONENT_NAMES: [&str; 4] = ["x", "y", "z", "w"];
+
+/// Parses a vector-wide property, such as `offset__min 0.0,-1.0`, into per-component values.
+/// A single value applies to all components.
+fn parse_component_values<T: FromStr + Clone>(
+    identifier: &str,
+    property_name: &str,
+    components: usize,
+    preprocess_result: &PreprocessResult,
+) -> Result<Option<Vec<T>>, Cow<'static, str>> {
+    let property_identifier = format!("{}__{}", identifier, property_name);
+    let raw = match preprocess_result.parse::<String>(&property_identifier) {
+        Some(raw) => raw?,
+        None => return Ok(None),
+    };
+    let values = raw.split(',')
+        .map(|value| value.trim().parse::<T>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            format!(
+                "Could not parse property `{}` as a comma-separated list of values of type `{}`.",
+                property_identifier,
+                std::any::type_name::<T>(),
+            )
+        })?;
+
+    match values.len() {
+        1 => Ok(Some(vec![values[0].clone(); components])),
+        len if len == components => Ok(Some(values)),
+        len => throw!(format!(
+            "Property `{}` has {} components, expected either 1 or {}.",
+            property_identifier,
+            len,
+            components,
+        )),
+    }
+}
+
+/// Defines custom effect params for multi-component uniforms.
+/// Each component is bound to a separate property named `<identifier>__<component>`,
+/// so that properties like `<identifier>__x__min` may be specified per component.
+macro_rules! define_effect_param_custom_vectors {
+    ($(
+        $name:ident: [$component:ty; $len:expr] => $descriptor:ident<$specialization:ident> {
+            min: $min:expr,
+            max: $max:expr,
+            step: $step:expr$(,)?
+        }
+    ),*$(,)?) => {
+        item! {
+            $(
+                pub struct [< EffectParamCustom $name >] {
+                    pub effect_param: [< EffectParam $name >],
+                    pub properties: Vec<LoadedValueTypeProperty<$descriptor>>,
+                }
+
+                impl [< EffectParamCustom $name >] {
+                    fn get_value(&self) -> [$component; $len] {
+                        let mut value: [$component; $len] = Default::default();
+
+                        for (component, property) in value.iter_mut().zip(&self.properties) {
+                            *component = property.get_value() as $component;
+                        }
+
+                        value
+                    }
+                }
+
+                impl EffectParamCustom for [< EffectParamCustom $name >] {
+                    type ShaderParamType = [< ShaderParamType $name >];
+                    type PropertyDescriptorSpecialization = $specialization;
+
+                    fn new<'a>(
+                        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<Self::ShaderParamType>>,
+                        identifier: &str,
+                        settings: &mut SettingsContext,
+                        preprocess_result: &PreprocessResult,
+                    ) -> Result<Self, Cow<'static, str>> {
+                        let param_value_default: [$component; $len] = param.get_param_value_default()
+                            .cloned()
+                            .unwrap_or_default();
+                        type Value = <$specialization as ValuePropertyDescriptorSpecialization>::ValueType;
+
+                        let defaults = parse_component_values::<Value>(identifier, "default", $len, preprocess_result)?;
+                        let mins = parse_component_values::<Value>(identifier, "min", $len, preprocess_result)?;
+                        let maxs = parse_component_values::<Value>(identifier, "max", $len, preprocess_result)?;
+                        let steps = parse_component_values::<Value>(identifier, "step", $len, preprocess_result)?;
+                        let sliders = parse_component_values::<bool>(identifier, "slider", $len, preprocess_result)?;
+                        let mut properties = Vec::with_capacity($len);
+
+                        for (index, component_name) in VECTOR_COMPONENT_NAMES.iter().take($len).enumerate() {
+                            properties.push(<LoadedValueTypeProperty<_> as LoadedValueType>::from(
+                                LoadedValueTypePropertyArgs {
+                                    allow_definitions_in_source: true,
+                                    default_value: defaults.as_ref()
+                                        .map(|defaults| defaults[index])
+                                        .unwrap_or(param_value_default[index].into()),
+                                    default_descriptor_specialization: $specialization {
+                                        min: mins.as_ref().map(|mins| mins[index]).unwrap_or($min),
+                                        max: maxs.as_ref().map(|maxs| maxs[index]).unwrap_or($max),
+                                        step: steps.as_ref().map(|steps| steps[index]).unwrap_or($step),
+                                        slider: sliders.as_ref().map(|sliders| sliders[index]).unwrap_or(false),
+                                    },
+                                },
+                                identifier,
+                                Some(*component_name),
+                                preprocess_result,
+                                settings,
+                            )?);
+                        }
+
+                        let mut result = Self {
+                            effect_param: EffectParam::new(param.disable()),
+                            properties,
+                        };
+
+                        result.effect_param.prepare_value(result.get_value());
+
+                        Ok(result)
+                    }
+                }
+
+                impl BindableProperty for [< EffectParamCustom $name >] {
+                    fn add_properties(&self, properties: &mut Properties) {
+                        self.properties.iter().for_each(|property| property.add_properties(properties));
+                    }
+
+                    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+                        self.properties.iter_mut().for_each(|property| property.reload_settings(settings));
+                        self.effect_param.prepare_value(self.get_value());
+                    }
+
+                    fn prepare_values(&mut self) {}
+
+                    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+                        self.effect_param.stage_value(graphics_context);
+                    }
+
+                    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+                        self.effect_param.assign_value(graphics_context);
+                    }
+
+                    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+                        self.effect_param.enable_and_drop(graphics_context);
+                    }
+                }
+            )*
+        }
+    }
+}
+
+define_effect_param_custom_vectors! {
+    Vec2: [f32; 2] => LoadedValueTypePropertyDescriptorF64<PropertyDescriptorSpecializationF64> {
+        min: std::f64::MIN,
+        max: std::f64::MAX,
+        step: 0.1,
+    },
+    Vec3: [f32; 3] => LoadedValueTypePropertyDescriptorF64<PropertyDescriptorSpecializationF64> {
+        min: std::f64::MIN,
+        max: std::f64::MAX,
+        step: 0.1,
+    },
+    IVec2: [i32; 2] => LoadedValueTypePropertyDescriptorI32<PropertyDescriptorSpecializationI32> {
+        min: std::i32::MIN,
+        max: std::i32::MAX,
+        step: 1,
+    },
+    IVec3: [i32; 3] => LoadedValueTypePropertyDescriptorI32<PropertyDescriptorSpecializationI32> {
+        min: std::i32::MIN,
+        max: std::i32::MAX,
+        step: 1,
+    },
+    IVec4: [i32; 4] => LoadedValueTypePropertyDescriptorI32<PropertyDescriptorSpecializationI32> {
+        min: std::i32::MIN,
+        max: std::i32::MAX,
+        step: 1,
+    },
+}
+
+pub struct EffectParamCustomImage {
     pub effect_param: EffectParamTexture,
-    pub effect_param_previous: Option<EffectParamTexture>,
-    pub audio_fft: Option<Arc<GlobalStateAudioFFT>>,
+    pub property: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>,
+    /// Relative paths are resolved relative to the directory of the shader.
+    pub shader_directory: PathBuf,
+    pub loaded_path: Option<PathBuf>,
+    pub watcher: Option<FileWatcher>,
+    pub error: Option<String>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomImage {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new<'a>(
+        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
+        identifier: &str,
+        shader_directory: &Path,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: PathBuf::new(),
+                default_descriptor_specialization: PropertyDescriptorSpecializationPath {
+                    path_type: PathType::File,
+                    filter: CString::new("Image Files (*.png *.jpg *.jpeg *.bmp);;All Files (*.*)").unwrap(),
+                    default_path: CString::new(shader_directory.to_string_lossy().as_bytes())
+                        .map_err(|_| "Shader path cannot be converted to a C string.")?,
+                },
+            },
+            identifier,
+            None,
+            preprocess_result,
+            settings,
+        )?;
+        let mut result = Self {
+            effect_param: EffectParam::new(param.disable()),
+            property,
+            shader_directory: shader_directory.to_path_buf(),
+            loaded_path: None,
+            watcher: None,
+            error: None,
+        };
+
+        result.load_image();
+
+        Ok(result)
+    }
+
+    fn resolve_path(&self) -> Option<PathBuf> {
+        let path = self.property.get_value();
+
+        if path.as_os_str().is_empty() {
+            None
+        } else {
+            Some(self.shader_directory.join(path))
+        }
+    }
+
+    /// Decodes the selected image and prepares it to be uploaded.
+    /// If no image is selected or it cannot be decoded, a transparent texture is used instead.
+    fn load_image(&mut self) {
+        let path = self.resolve_path();
+        let texture = path.as_ref().map(|path| TextureDescriptor::from_image_file(path));
+
+        self.watcher = path.clone().map(|path| FileWatcher::new(Some(path), Self::POLL_INTERVAL));
+        self.loaded_path = path;
+        self.error = None;
+
+        match texture {
+            Some(Ok(texture)) => self.effect_param.prepare_value(texture),
+            Some(Err(error)) => {
+                self.error = Some(error.into_owned());
+                self.effect_param.prepare_value(Default::default());
+            },
+            None => self.effect_param.prepare_value(Default::default()),
+        }
+    }
+}
+
+impl BindableProperty for EffectParamCustomImage {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.property.add_properties(properties);
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.property.reload_settings(settings);
+
+        if self.resolve_path() != self.loaded_path {
+            self.load_image();
+        }
+    }
+
+    fn prepare_values(&mut self) {
+        let changed = self.watcher.as_mut().map(|watcher| watcher.poll()).unwrap_or(false);
+
+        if changed {
+            self.load_image();
+        }
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        self.effect_param.stage_value(graphics_context);
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        self.effect_param.assign_value(graphics_context);
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        self.effect_param.enable_and_drop(graphics_context);
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.error.as_ref().map(|error| Cow::Borrowed(error.as_str()))
+    }
+}
+
+/// A texture rendered from another source, picked by the user from a list of sources.
+/// The dimensions of the source are bound to `builtin_uv_size_<NAME>`, if it is defined.
+pub struct EffectParamCustomSource {
+    pub effect_param: EffectParamTexture,
+    pub size_param: Option<EffectParamIVec2>,
+    pub identifier: String,
+    pub description: LoadedValueTypeSource<String>,
+    /// Used to read the selected source from the settings
+    pub descriptor: PropertyDescriptor<PropertyDescriptorSpecializationString>,
+    pub default_source: CString,
+    pub source: SourceReference,
+    pub texture_render: Option<SourceTextureRender>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomSource {
+    pub fn new<'a>(
+        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
+        size_param: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeIVec2>>>,
+        identifier: &str,
+        default_source: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let description = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(identifier.to_string()),
+            },
+            identifier,
+            Some("description"),
+            preprocess_result,
+            settings,
+        )?;
+        let descriptor = PropertyDescriptor {
+            name: CString::new(identifier).unwrap(),
+            description: CString::new(description.get_value().unwrap()).unwrap(),
+            specialization: PropertyDescriptorSpecializationString {
+                string_type: StringType::Default,
+            },
+        };
+        let default_source = CString::new(default_source)
+            .map_err(|_| "The default source name cannot be converted to a C string.")?;
+        let source = SourceReference::new(settings.get_property_value(&descriptor, &default_source));
+
+        Ok(Self {
+            effect_param: EffectParam::new(param.disable()),
+            size_param: size_param.map(|size_param| EffectParam::new(size_param.disable())),
+            identifier: identifier.to_string(),
+            description,
+            descriptor,
+            default_source,
+            source,
+            texture_render: None,
+        })
+    }
+}
+
+impl BindableProperty for EffectParamCustomSource {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.description.add_properties(properties);
+        properties.add_property(&PropertyDescriptor {
+            name: self.descriptor.name.clone(),
+            description: self.descriptor.description.clone(),
+            specialization: PropertyDescriptorSpecializationSourceList {
+                output_flags: OBS_SOURCE_VIDEO,
+                selected: Some(self.source.name().to_owned()),
+            },
+        });
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.source.set_name(settings.get_property_value(&self.descriptor, &self.default_source));
+    }
+
+    fn prepare_values(&mut self) {
+        self.source.update();
+
+        if let Some(size_param) = self.size_param.as_mut() {
+            let [width, height] = self.source.dimensions();
+
+            size_param.prepare_value([width as i32, height as i32]);
+        }
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        self.effect_param.stage_value(graphics_context);
+
+        if let Some(size_param) = self.size_param.as_mut() {
+            size_param.stage_value(graphics_context);
+        }
+    }
+
+    fn render(&mut self, _graphics_context: &GraphicsContext) {
+        let texture_render = self.texture_render.get_or_insert_with(SourceTextureRender::new);
+        let source = self.source.get();
+
+        texture_render.render(source);
+
+        if !source.is_null() {
+            unsafe {
+                obs_source_release(source);
+            }
+        }
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        let texture = self.texture_render.as_ref()
+            .map(|texture_render| texture_render.texture())
+            .unwrap_or(std::ptr::null_mut());
+
+        if texture.is_null() {
+            // Fall back to the transparent default texture
+            self.effect_param.assign_value(graphics_context);
+        } else {
+            let param = self.effect_param.param.as_enabled(graphics_context.graphics());
+
+            unsafe {
+                gs_effect_set_texture(param.as_ptr(), texture);
+            }
+        }
+
+        if let Some(size_param) = self.size_param.as_mut() {
+            size_param.assign_value(graphics_context);
+        }
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        self.effect_param.enable_and_drop(graphics_context);
+
+        if let Some(size_param) = self.size_param {
+            size_param.enable_and_drop(graphics_context);
+        }
+
+        // The texture must be destroyed within the graphics context
+        std::mem::drop(self.texture_render);
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        if self.source.is_missing() {
+            Some(Cow::Owned(format!(
+                "The source `{}` selected for `{}` could not be found.",
+                self.source.name().to_string_lossy(),
+                self.identifier,
+            )))
+        } else {
+            None
+        }
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        if self.source.name().to_bytes() != previous_name.as_bytes() {
+            return false;
+        }
+
+        let new_name = CString::new(new_name).unwrap_or_default();
+
+        settings.set_property_value(&self.descriptor, new_name.clone());
+        self.source.set_name(new_name);
+        true
+    }
+}
+
+/// Creates the property for picking a source, whose audio is analysed instead of the selected mix.
+fn audio_source_property(
+    identifier: &str,
+    settings: &mut SettingsContext,
+    preprocess_result: &PreprocessResult,
+) -> Result<LoadedValueTypeSourceList, Cow<'static, str>> {
+    <LoadedValueTypeSourceList as LoadedValueType>::from(
+        LoadedValueTypeSourceListArgs {
+            output_flags: OBS_SOURCE_AUDIO,
+        },
+        identifier,
+        Some("audio_source"),
+        preprocess_result,
+        settings,
+    )
+}
+
+/// Creates the property for picking a WAV file, which is played in a loop and analysed instead of live audio.
+fn audio_file_property(
+    identifier: &str,
+    settings: &mut SettingsContext,
+    preprocess_result: &PreprocessResult,
+) -> Result<LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>, Cow<'static, str>> {
+    <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+        LoadedValueTypePropertyArgs {
+            allow_definitions_in_source: false,
+            default_value: PathBuf::new(),
+            default_descriptor_specialization: PropertyDescriptorSpecializationPath {
+                path_type: PathType::File,
+                filter: CString::new("WAV Files (*.wav);;All Files (*.*)").unwrap(),
+                default_path: CString::default(),
+            },
+        },
+        identifier,
+        Some("audio_file"),
+        preprocess_result,
+        settings,
+    )
+}
+
+/// The selected audio file is played, if there is one, otherwise the audio of the selected source is captured,
+/// if there is one, otherwise the audio of the mix.
+fn audio_input_descriptor(
+    property_mix: &LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    property_audio_source: &LoadedValueTypeSourceList,
+    property_audio_file: &LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>,
+) -> AudioInputDescriptor {
+    let audio_source = property_audio_source.get_value();
+    let audio_file = property_audio_file.get_value();
+
+    if !audio_file.as_os_str().is_empty() {
+        AudioInputDescriptor::File(audio_file)
+    } else if !audio_source.is_empty() {
+        AudioInputDescriptor::Source(audio_source)
+    } else {
+        AudioInputDescriptor::Mix(property_mix.get_value() as usize - 1)
+    }
+}
+
+/// The properties of the FFT analysis, shared by the builtin textures derived from the frequency spectrum.
+pub struct EffectParamCustomFFTProperties {
+    pub identifier: String,
     pub property_mix: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_audio_source: LoadedValueTypeSourceList,
+    pub property_audio_file: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>,
     pub property_channel: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
     pub property_dampening_factor_attack: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
     pub property_dampening_factor_release: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_window_function: LoadedValueTypeList,
+    /// Coefficients of the `cosine` window function
+    pub property_window_cosine_coefficients: Vec<LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>>,
+    pub property_fft_size: LoadedValueTypeList,
+    pub property_hop_size: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_zero_padding: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_scale: LoadedValueTypeList,
+    /// The decibels mapped to `0` by the `decibels` scale
+    pub property_db_floor: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    /// The decibels mapped to `1` by the `decibels` scale
+    pub property_db_ceiling: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_frequency_mapping: LoadedValueTypeList,
+    /// Selects the Mel frequency mapping, predates `property_frequency_mapping`
     pub property_mel_enabled: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorBool>,
     pub property_n_mels: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
     pub property_f_min: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
     pub property_f_max: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_mel_scale: LoadedValueTypeList,
+    pub property_mel_formula: LoadedValueTypeList,
+    pub property_mel_area_normalization: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorBool>,
+    pub property_log_bins_per_octave: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_log_f_min: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_log_f_max: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
 }
 
-// Does not implement EffectParamCustom because of different argument requirements
-impl EffectParamCustomFFT {
-    pub fn new<'a>(
-        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
-        param_previous: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>>,
+impl EffectParamCustomFFTProperties {
+    pub fn new(
         identifier: &str,
         settings: &mut SettingsContext,
         preprocess_result: &PreprocessResult,
@@ -504,6 +1065,8 @@ impl EffectParamCustomFFT {
             preprocess_result,
             settings,
         )?;
+        let property_audio_source = audio_source_property(identifier, settings, preprocess_result)?;
+        let property_audio_file = audio_file_property(identifier, settings, preprocess_result)?;
         let property_channel = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
             LoadedValueTypePropertyArgs {
                 allow_definitions_in_source: false,
@@ -552,6 +1115,131 @@ impl EffectParamCustomFFT {
             preprocess_result,
             settings,
         )?;
+        let property_window_function = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "hanning",
+                items: WindowFunction::ITEMS,
+            },
+            identifier,
+            Some("window_function"),
+            preprocess_result,
+            settings,
+        )?;
+        // Defaults to the coefficients of the Hanning window
+        let property_window_cosine_coefficients = [("a", 0.5), ("b", 0.5), ("c", 0.0), ("d", 0.0)].iter()
+            .map(|(coefficient_name, default_value)| {
+                <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+                    LoadedValueTypePropertyArgs {
+                        allow_definitions_in_source: true,
+                        default_value: *default_value,
+                        default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                            min: -10.0,
+                            max: 10.0,
+                            step: 0.001,
+                            slider: false,
+                        },
+                    },
+                    identifier,
+                    Some(&format!("window_cosine_{}", coefficient_name)),
+                    preprocess_result,
+                    settings,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let property_fft_size = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "0",
+                items: SpectrumAnalyzerParameters::FFT_SIZE_ITEMS,
+            },
+            identifier,
+            Some("fft_size"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_hop_size = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 0,
+                    max: 65536,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("hop_size"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_zero_padding = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 1,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 16,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("zero_padding"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_scale = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "sqrt_magnitude",
+                items: SpectrumScale::ITEMS,
+            },
+            identifier,
+            Some("scale"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_db_floor = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: -80.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: -200.0,
+                    max: 50.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("db_floor"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_db_ceiling = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 0.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: -200.0,
+                    max: 50.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("db_ceiling"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_frequency_mapping = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "linear",
+                items: FrequencyMapping::ITEMS,
+            },
+            identifier,
+            Some("frequency_mapping"),
+            preprocess_result,
+            settings,
+        )?;
         let property_mel_enabled = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
             LoadedValueTypePropertyArgs {
                 allow_definitions_in_source: true,
@@ -611,76 +1299,394 @@ impl EffectParamCustomFFT {
             preprocess_result,
             settings,
         )?;
-
-        let audio_fft_descriptor = GlobalStateAudioFFTDescriptor::new_with_mel(
-            property_mix.get_value() as usize - 1,
-            property_channel.get_value() as usize - 1,
-            property_dampening_factor_attack.get_value() / 100.0,
-            property_dampening_factor_release.get_value() / 100.0,
-            WindowFunction::Hanning,
-            property_mel_enabled.get_value(),
-            property_n_mels.get_value() as usize,
-            property_f_min.get_value() as f32,
-            property_f_max.get_value() as f32,
-        );
-
-        let mut result = Self {
-            effect_param: EffectParam::new(param.disable()),
-            effect_param_previous: param_previous.map(|param_previous| EffectParam::new(param_previous.disable())),
-            audio_fft: None,
+        let property_mel_scale = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "normalized",
+                items: SpectrumScale::ITEMS,
+            },
+            identifier,
+            Some("mel_scale"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_mel_formula = <LoadedValueTypeList as LoadedValueType>::from(
+            LoadedValueTypeListArgs {
+                default_value: "htk",
+                items: MelFormula::ITEMS,
+            },
+            identifier,
+            Some("mel_formula"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_mel_area_normalization = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: false,
+                default_descriptor_specialization: PropertyDescriptorSpecializationBool {},
+            },
+            identifier,
+            Some("mel_area_normalization"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_log_bins_per_octave = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 3,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 48,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("log_bins_per_octave"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_log_f_min = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 20.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 1.0,
+                    max: 24000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("log_f_min"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_log_f_max = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 20000.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 1.0,
+                    max: 24000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("log_f_max"),
+            preprocess_result,
+            settings,
+        )?;
+
+        Ok(Self {
+            identifier: identifier.to_string(),
             property_mix,
+            property_audio_source,
+            property_audio_file,
             property_channel,
             property_dampening_factor_attack,
             property_dampening_factor_release,
+            property_window_function,
+            property_window_cosine_coefficients,
+            property_fft_size,
+            property_hop_size,
+            property_zero_padding,
+            property_scale,
+            property_db_floor,
+            property_db_ceiling,
+            property_frequency_mapping,
             property_mel_enabled,
             property_n_mels,
             property_f_min,
             property_f_max,
-        };
+            property_mel_scale,
+            property_mel_formula,
+            property_mel_area_normalization,
+            property_log_bins_per_octave,
+            property_log_f_min,
+            property_log_f_max,
+        })
+    }
 
-        result.request_audio_fft();
+    fn window_function(&self) -> WindowFunction {
+        let mut cosine_coefficients = [0.0; 4];
 
-        Ok(result)
+        for (coefficient, property) in cosine_coefficients.iter_mut().zip(&self.property_window_cosine_coefficients) {
+            *coefficient = property.get_value();
+        }
+
+        WindowFunction::from_name(&self.property_window_function.get_value(), cosine_coefficients)
+            .unwrap_or(WindowFunction::Hanning)
     }
 
-    fn request_audio_fft(&mut self) {
-        let audio_fft_descriptor = GlobalStateAudioFFTDescriptor::new_with_mel(
-            self.property_mix.get_value() as usize - 1,
+    fn scale(&self, property: &LoadedValueTypeList, default_value: SpectrumScale) -> SpectrumScale {
+        let decibel_range = [
+            self.property_db_floor.get_value() as f32,
+            self.property_db_ceiling.get_value() as f32,
+        ];
+
+        SpectrumScale::from_name(&property.get_value(), decibel_range).unwrap_or(default_value)
+    }
+
+    pub fn frequency_mapping(&self) -> FrequencyMapping {
+        let frequency_mapping = FrequencyMapping::from_name(&self.property_frequency_mapping.get_value())
+            .unwrap_or(FrequencyMapping::Linear);
+
+        if frequency_mapping == FrequencyMapping::Linear && self.property_mel_enabled.get_value() {
+            FrequencyMapping::Mel
+        } else {
+            frequency_mapping
+        }
+    }
+
+    fn log_frequency_parameters(&self) -> LogFrequencyParameters {
+        LogFrequencyParameters {
+            bins_per_octave: self.property_log_bins_per_octave.get_value().max(1) as usize,
+            f_min: self.property_log_f_min.get_value() as f32,
+            f_max: self.property_log_f_max.get_value() as f32,
+        }
+    }
+
+    pub fn descriptor(&self) -> GlobalStateAudioFFTDescriptor {
+        let frequency_mapping = self.frequency_mapping();
+        let log_frequency_parameters = self.log_frequency_parameters();
+
+        GlobalStateAudioFFTDescriptor::new_with_mel(
+            audio_input_descriptor(&self.property_mix, &self.property_audio_source, &self.property_audio_file),
             self.property_channel.get_value() as usize - 1,
             self.property_dampening_factor_attack.get_value() / 100.0,
             self.property_dampening_factor_release.get_value() / 100.0,
-            WindowFunction::Hanning,
-            self.property_mel_enabled.get_value(),
+            self.window_function(),
+            frequency_mapping == FrequencyMapping::Mel,
             self.property_n_mels.get_value() as usize,
             self.property_f_min.get_value() as f32,
             self.property_f_max.get_value() as f32,
-        );
+        ).with_sizes(
+            SpectrumAnalyzerParameters::fft_size_from_name(&self.property_fft_size.get_value()).unwrap_or(0),
+            self.property_hop_size.get_value().max(0) as usize,
+            self.property_zero_padding.get_value().max(1) as usize,
+        ).with_scales(
+            self.scale(&self.property_scale, SpectrumScale::SqrtMagnitude),
+            self.scale(&self.property_mel_scale, SpectrumScale::Normalized),
+        ).with_mel_filters(
+            MelFormula::from_name(&self.property_mel_formula.get_value()).unwrap_or(MelFormula::Htk),
+            self.property_mel_area_normalization.get_value(),
+        ).with_log_frequencies(
+            frequency_mapping == FrequencyMapping::Log,
+            log_frequency_parameters.bins_per_octave,
+            log_frequency_parameters.f_min,
+            log_frequency_parameters.f_max,
+        )
+    }
+
+    /// An invalid frequency range of the logarithmic frequency mapping, if it is used.
+    pub fn message(&self) -> Option<Cow<str>> {
+        if self.frequency_mapping() != FrequencyMapping::Log {
+            return None;
+        }
 
-        self.audio_fft = Some(GLOBAL_STATE.request_audio_fft(&audio_fft_descriptor));
+        self.log_frequency_parameters().validate().err().map(|error| {
+            Cow::Owned(format!("The logarithmic frequency bands of `{}` are invalid: {}", self.identifier, error))
+        })
     }
-}
 
-impl BindableProperty for EffectParamCustomFFT {
-    fn add_properties(&self, properties: &mut Properties) {
+    pub fn add_properties(&self, properties: &mut Properties) {
         self.property_mix.add_properties(properties);
+        self.property_audio_source.add_properties(properties);
+        self.property_audio_file.add_properties(properties);
         self.property_channel.add_properties(properties);
         self.property_dampening_factor_attack.add_properties(properties);
         self.property_dampening_factor_release.add_properties(properties);
+        self.property_window_function.add_properties(properties);
+        self.property_window_cosine_coefficients.iter().for_each(|property| property.add_properties(properties));
+        self.property_fft_size.add_properties(properties);
+        self.property_hop_size.add_properties(properties);
+        self.property_zero_padding.add_properties(properties);
+        self.property_scale.add_properties(properties);
+        self.property_db_floor.add_properties(properties);
+        self.property_db_ceiling.add_properties(properties);
+        self.property_frequency_mapping.add_properties(properties);
         self.property_mel_enabled.add_properties(properties);
         self.property_n_mels.add_properties(properties);
         self.property_f_min.add_properties(properties);
         self.property_f_max.add_properties(properties);
+        self.property_mel_scale.add_properties(properties);
+        self.property_mel_formula.add_properties(properties);
+        self.property_mel_area_normalization.add_properties(properties);
+        self.property_log_bins_per_octave.add_properties(properties);
+        self.property_log_f_min.add_properties(properties);
+        self.property_log_f_max.add_properties(properties);
     }
 
-    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+    pub fn reload_settings(&mut self, settings: &mut SettingsContext) {
         self.property_mix.reload_settings(settings);
+        self.property_audio_source.reload_settings(settings);
+        self.property_audio_file.reload_settings(settings);
         self.property_channel.reload_settings(settings);
         self.property_dampening_factor_attack.reload_settings(settings);
         self.property_dampening_factor_release.reload_settings(settings);
+        self.property_window_function.reload_settings(settings);
+        self.property_window_cosine_coefficients.iter_mut().for_each(|property| property.reload_settings(settings));
+        self.property_fft_size.reload_settings(settings);
+        self.property_hop_size.reload_settings(settings);
+        self.property_zero_padding.reload_settings(settings);
+        self.property_scale.reload_settings(settings);
+        self.property_db_floor.reload_settings(settings);
+        self.property_db_ceiling.reload_settings(settings);
+        self.property_frequency_mapping.reload_settings(settings);
         self.property_mel_enabled.reload_settings(settings);
         self.property_n_mels.reload_settings(settings);
         self.property_f_min.reload_settings(settings);
         self.property_f_max.reload_settings(settings);
+        self.property_mel_scale.reload_settings(settings);
+        self.property_mel_formula.reload_settings(settings);
+        self.property_mel_area_normalization.reload_settings(settings);
+        self.property_log_bins_per_octave.reload_settings(settings);
+        self.property_log_f_min.reload_settings(settings);
+        self.property_log_f_max.reload_settings(settings);
+    }
+
+    pub fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.property_audio_source.rename_source(settings, previous_name, new_name)
+    }
+}
+
+/// The values of an FFT analysis provided by an FFT texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FFTTextureKind {
+    /// The frequency spectrum or the Mel spectrum, `builtin_texture_fft_<NAME>`
+    Spectrum,
+    /// The Mel-frequency cepstral coefficients, `builtin_texture_mfcc_<NAME>`
+    Mfcc,
+    /// The energies of the 12 pitch classes, `builtin_texture_chroma_<NAME>`
+    Chroma,
+}
+
+/// Creates a texture with a single row of values.
+fn r32f_texture(values: &[f32]) -> TextureDescriptor {
+    let texture_data = unsafe {
+        std::slice::from_raw_parts::<u8>(
+            values.as_ptr() as *const _,
+            values.len() * std::mem::size_of::<f32>(),
+        )
+    }.iter().copied().collect::<Vec<_>>();
+
+    TextureDescriptor {
+        dimensions: [values.len(), 1],
+        color_format: ColorFormatKind::R32F,
+        levels: smallvec![texture_data],
+        flags: 0,
+    }
+}
+
+pub struct EffectParamCustomFFT {
+    pub kind: FFTTextureKind,
+    pub effect_param: EffectParamTexture,
+    pub effect_param_previous: Option<EffectParamTexture>,
+    /// The number of values in the texture
+    pub param_size: Option<EffectParamInt>,
+    pub param_sample_rate: Option<EffectParamFloat>,
+    /// The frequencies in Hz of the first and the last value in the texture
+    pub param_frequency_range: Option<EffectParamVec2>,
+    /// The discriminant of the `FrequencyMapping`
+    pub param_mode: Option<EffectParamInt>,
+    pub audio_fft: Option<Arc<GlobalStateAudioFFT>>,
+    pub properties: EffectParamCustomFFTProperties,
+    /// Only used by MFCC textures
+    pub property_mfcc_coefficients: Option<LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>>,
+}
+
+/// The uniform variables describing the frequency mapping of an FFT texture, all of which are optional.
+#[derive(Default)]
+pub struct EffectParamCustomFFTMetadataParams<'a> {
+    pub size: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeInt>>>,
+    pub sample_rate: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeFloat>>>,
+    pub frequency_range: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeVec2>>>,
+    pub mode: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeInt>>>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomFFT {
+    pub fn new<'a>(
+        kind: FFTTextureKind,
+        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
+        param_previous: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>>,
+        metadata_params: EffectParamCustomFFTMetadataParams<'a>,
+        identifier: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property_mfcc_coefficients = if kind == FFTTextureKind::Mfcc {
+            Some(<LoadedValueTypeProperty<_> as LoadedValueType>::from(
+                LoadedValueTypePropertyArgs {
+                    allow_definitions_in_source: true,
+                    default_value: 13,
+                    default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                        min: 1,
+                        max: 128,
+                        step: 1,
+                        slider: false,
+                    },
+                },
+                identifier,
+                Some("mfcc_coefficients"),
+                preprocess_result,
+                settings,
+            )?)
+        } else {
+            None
+        };
+
+        let mut result = Self {
+            kind,
+            effect_param: EffectParam::new(param.disable()),
+            effect_param_previous: param_previous.map(|param_previous| EffectParam::new(param_previous.disable())),
+            param_size: metadata_params.size.map(|param| EffectParam::new(param.disable())),
+            param_sample_rate: metadata_params.sample_rate.map(|param| EffectParam::new(param.disable())),
+            param_frequency_range: metadata_params.frequency_range.map(|param| EffectParam::new(param.disable())),
+            param_mode: metadata_params.mode.map(|param| EffectParam::new(param.disable())),
+            audio_fft: None,
+            properties: EffectParamCustomFFTProperties::new(identifier, settings, preprocess_result)?,
+            property_mfcc_coefficients,
+        };
+
+        result.request_audio_fft();
+
+        Ok(result)
+    }
+
+    fn request_audio_fft(&mut self) {
+        let mfcc_coefficients = self.property_mfcc_coefficients.as_ref()
+            .map(|property| property.get_value().max(1) as usize)
+            .unwrap_or(0);
+        let descriptor = self.properties.descriptor()
+            .with_features(mfcc_coefficients, self.kind == FFTTextureKind::Chroma);
+
+        self.audio_fft = Some(GLOBAL_STATE.request_audio_fft(&descriptor));
+    }
+
+    fn metadata_params_int_mut(&mut self) -> impl Iterator<Item=&mut EffectParamInt> {
+        self.param_size.iter_mut().chain(self.param_mode.iter_mut())
+    }
+}
+
+impl BindableProperty for EffectParamCustomFFT {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.properties.add_properties(properties);
+
+        if let Some(property_mfcc_coefficients) = self.property_mfcc_coefficients.as_ref() {
+            property_mfcc_coefficients.add_properties(properties);
+        }
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.properties.reload_settings(settings);
+
+        if let Some(property_mfcc_coefficients) = self.property_mfcc_coefficients.as_mut() {
+            property_mfcc_coefficients.reload_settings(settings);
+        }
+
         self.request_audio_fft();
     }
 
@@ -690,45 +1696,38 @@ impl BindableProperty for EffectParamCustomFFT {
         } else {
             return;
         };
-        
-        // Check if Mel is enabled in the FFT result
-        if self.property_mel_enabled.get_value() && fft_result.mel_spectrum.is_some() {
-            // Generate texture from Mel data
-            let mel_spectrum = fft_result.mel_spectrum.as_ref().unwrap();
-            let texture_data = unsafe {
-                std::slice::from_raw_parts::<u8>(
-                    mel_spectrum.as_ptr() as *const _,
-                    mel_spectrum.len() * std::mem::size_of::<f32>(),
-                )
-            }.iter().copied().collect::<Vec<_>>();
-            
-            let texture_mel = TextureDescriptor {
-                dimensions: [mel_spectrum.len(), 1],
-                color_format: ColorFormatKind::R32F,
-                levels: smallvec![texture_data],
-                flags: 0,
-            };
-            
-            self.effect_param.prepare_value(texture_mel);
-        } else {
-            // Use standard FFT data
-            let frequency_spectrum = &fft_result.frequency_spectrum;
-            let texture_data = unsafe {
-                std::slice::from_raw_parts::<u8>(
-                    frequency_spectrum.as_ptr() as *const _,
-                    frequency_spectrum.len() * std::mem::size_of::<f32>(),
-                )
-            }.iter().copied().collect::<Vec<_>>();
-            
-            let texture_fft = TextureDescriptor {
-                dimensions: [frequency_spectrum.len(), 1],
-                color_format: ColorFormatKind::R32F,
-                levels: smallvec![texture_data],
-                flags: 0,
-            };
-            
-            self.effect_param.prepare_value(texture_fft);
+        let frequency_mapping = self.properties.frequency_mapping();
+        let values = match self.kind {
+            FFTTextureKind::Spectrum => fft_result.spectrum(frequency_mapping).clone(),
+            FFTTextureKind::Mfcc => if let Some(mfcc) = fft_result.mfcc {
+                mfcc
+            } else {
+                return;
+            },
+            FFTTextureKind::Chroma => if let Some(chroma) = fft_result.chroma {
+                chroma
+            } else {
+                return;
+            },
+        };
+
+        if let Some(param_size) = self.param_size.as_mut() {
+            param_size.prepare_value(values.len() as i32);
+        }
+
+        if let Some(param_sample_rate) = self.param_sample_rate.as_mut() {
+            param_sample_rate.prepare_value(fft_result.sample_rate as f32);
+        }
+
+        if let Some(param_frequency_range) = self.param_frequency_range.as_mut() {
+            param_frequency_range.prepare_value(fft_result.frequency_range(frequency_mapping));
+        }
+
+        if let Some(param_mode) = self.param_mode.as_mut() {
+            param_mode.prepare_value(frequency_mapping as i32);
         }
+
+        self.effect_param.prepare_value(r32f_texture(&values));
     }
 
     fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
@@ -739,6 +1738,15 @@ impl BindableProperty for EffectParamCustomFFT {
         }
 
         self.effect_param.stage_value(graphics_context);
+        self.metadata_params_int_mut().for_each(|param| param.stage_value(graphics_context));
+
+        if let Some(param_sample_rate) = self.param_sample_rate.as_mut() {
+            param_sample_rate.stage_value(graphics_context);
+        }
+
+        if let Some(param_frequency_range) = self.param_frequency_range.as_mut() {
+            param_frequency_range.stage_value(graphics_context);
+        }
     }
 
     fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
@@ -746,12 +1754,953 @@ impl BindableProperty for EffectParamCustomFFT {
             effect_param_previous.assign_value_if_staged(graphics_context);
         }
         self.effect_param.assign_value_if_staged(graphics_context);
+        self.metadata_params_int_mut().for_each(|param| param.assign_value(graphics_context));
+
+        if let Some(param_sample_rate) = self.param_sample_rate.as_mut() {
+            param_sample_rate.assign_value(graphics_context);
+        }
+
+        if let Some(param_frequency_range) = self.param_frequency_range.as_mut() {
+            param_frequency_range.assign_value(graphics_context);
+        }
     }
 
     fn enable_and_drop(self, graphics_context: &GraphicsContext) {
-        if let Some(effect_param_previous) = self.effect_param_previous {
+        let Self { effect_param, effect_param_previous, param_size, param_sample_rate, param_frequency_range, param_mode, .. } = self;
+
+        if let Some(effect_param_previous) = effect_param_previous {
             effect_param_previous.enable_and_drop(graphics_context);
         }
-        self.effect_param.enable_and_drop(graphics_context);
+        effect_param.enable_and_drop(graphics_context);
+
+        param_size.into_iter()
+            .chain(param_mode)
+            .for_each(|param| param.enable_and_drop(graphics_context));
+
+        if let Some(param_sample_rate) = param_sample_rate {
+            param_sample_rate.enable_and_drop(graphics_context);
+        }
+
+        if let Some(param_frequency_range) = param_frequency_range {
+            param_frequency_range.enable_and_drop(graphics_context);
+        }
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.properties.rename_source(settings, previous_name, new_name)
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.properties.message().or_else(|| {
+            self.audio_fft.as_ref()
+                .and_then(|audio_fft| audio_fft.audio_input_error())
+                .map(Cow::Owned)
+        })
+    }
+}
+
+pub struct EffectParamCustomSpectrogram {
+    pub effect_param: EffectParamTexture,
+    /// The index of the row containing the most recent spectrum
+    pub head_param: Option<EffectParamInt>,
+    pub audio_fft: Option<Arc<GlobalStateAudioFFT>>,
+    pub properties: EffectParamCustomFFTProperties,
+    pub property_history_length: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    /// The batch number of the last spectrum written into the texture
+    pub last_batch_number: Option<usize>,
+    /// A spectrum which has not yet been written into the texture
+    pub pending_spectrum: Option<Arc<Vec<f32>>>,
+    pub texture: Option<RingTexture>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomSpectrogram {
+    pub fn new<'a>(
+        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
+        head_param: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeInt>>>,
+        identifier: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property_history_length = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 256,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 4096,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("history_length"),
+            preprocess_result,
+            settings,
+        )?;
+
+        let mut result = Self {
+            effect_param: EffectParam::new(param.disable()),
+            head_param: head_param.map(|head_param| EffectParam::new(head_param.disable())),
+            audio_fft: None,
+            properties: EffectParamCustomFFTProperties::new(identifier, settings, preprocess_result)?,
+            property_history_length,
+            last_batch_number: None,
+            pending_spectrum: None,
+            texture: None,
+        };
+
+        result.request_audio_fft();
+
+        Ok(result)
+    }
+
+    fn request_audio_fft(&mut self) {
+        self.audio_fft = Some(GLOBAL_STATE.request_audio_fft(&self.properties.descriptor()));
+    }
+}
+
+impl BindableProperty for EffectParamCustomSpectrogram {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.properties.add_properties(properties);
+        self.property_history_length.add_properties(properties);
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.properties.reload_settings(settings);
+        self.property_history_length.reload_settings(settings);
+        self.request_audio_fft();
+    }
+
+    fn prepare_values(&mut self) {
+        let fft_result = if let Some(result) = self.audio_fft.as_ref().unwrap().retrieve_result() {
+            result
+        } else {
+            return;
+        };
+
+        // Only spectra which were not yet written into the history are kept
+        if self.last_batch_number == Some(fft_result.batch_number) {
+            return;
+        }
+
+        self.last_batch_number = Some(fft_result.batch_number);
+        self.pending_spectrum = Some(fft_result.spectrum(self.properties.frequency_mapping()).clone());
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        if let Some(spectrum) = self.pending_spectrum.take() {
+            let dimensions = [spectrum.len(), self.property_history_length.get_value().max(1) as usize];
+
+            // The history is cleared, when the size of the spectrum or the history length change
+            if self.texture.as_ref().map(|texture| texture.dimensions() != dimensions).unwrap_or(true) {
+                self.texture = Some(RingTexture::new(dimensions[0], dimensions[1]));
+            }
+
+            let texture = self.texture.as_mut().unwrap();
+
+            texture.push_row(&spectrum);
+
+            if let Some(head_param) = self.head_param.as_mut() {
+                head_param.prepare_value(texture.head() as i32);
+            }
+        }
+
+        self.effect_param.stage_value(graphics_context);
+
+        if let Some(head_param) = self.head_param.as_mut() {
+            head_param.stage_value(graphics_context);
+        }
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        if let Some(texture) = self.texture.as_ref() {
+            let param = self.effect_param.param.as_enabled(graphics_context.graphics());
+
+            unsafe {
+                gs_effect_set_texture(param.as_ptr(), texture.texture());
+            }
+        } else {
+            // Fall back to the default texture, until the first spectrum is available
+            self.effect_param.assign_value(graphics_context);
+        }
+
+        if let Some(head_param) = self.head_param.as_mut() {
+            head_param.assign_value(graphics_context);
+        }
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        self.effect_param.enable_and_drop(graphics_context);
+
+        if let Some(head_param) = self.head_param {
+            head_param.enable_and_drop(graphics_context);
+        }
+
+        // The texture must be destroyed within the graphics context
+        std::mem::drop(self.texture);
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.properties.rename_source(settings, previous_name, new_name)
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.properties.message().or_else(|| {
+            self.audio_fft.as_ref()
+                .and_then(|audio_fft| audio_fft.audio_input_error())
+                .map(Cow::Owned)
+        })
+    }
+}
+
+pub struct EffectParamCustomBeat {
+    /// Seconds since the last beat
+    pub param_time: Option<EffectParamFloat>,
+    pub param_count: Option<EffectParamInt>,
+    pub param_bpm: Option<EffectParamFloat>,
+    /// The position within the current beat, from `0` to `1`
+    pub param_phase: Option<EffectParamFloat>,
+    pub param_strength: Option<EffectParamFloat>,
+    /// Seconds since the last onset in each of the frequency bands
+    pub param_onsets: Option<EffectParamTexture>,
+    pub audio_beat: Option<Arc<GlobalStateAudioBeat>>,
+    pub properties: EffectParamCustomFFTProperties,
+    pub property_sensitivity: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_bands: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_band_f_min: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_band_f_max: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_bpm_min: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_bpm_max: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+}
+
+/// The uniform variables a beat detection is bound to, all of which are optional.
+#[derive(Default)]
+pub struct EffectParamCustomBeatParams<'a> {
+    pub time: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeFloat>>>,
+    pub count: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeInt>>>,
+    pub bpm: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeFloat>>>,
+    pub phase: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeFloat>>>,
+    pub strength: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeFloat>>>,
+    pub onsets: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomBeat {
+    pub fn new<'a>(
+        params: EffectParamCustomBeatParams<'a>,
+        identifier: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property_sensitivity = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 1.5,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 10.0,
+                    step: 0.01,
+                    slider: true,
+                },
+            },
+            identifier,
+            Some("sensitivity"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_bands = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 8,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 64,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("bands"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_band_f_min = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 20.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 1.0,
+                    max: 20000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("band_f_min"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_band_f_max = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 10000.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 1.0,
+                    max: 20000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("band_f_max"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_bpm_min = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 70.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 10.0,
+                    max: 400.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("bpm_min"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_bpm_max = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 180.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 10.0,
+                    max: 400.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("bpm_max"),
+            preprocess_result,
+            settings,
+        )?;
+
+        let mut result = Self {
+            param_time: params.time.map(|param| EffectParam::new(param.disable())),
+            param_count: params.count.map(|param| EffectParam::new(param.disable())),
+            param_bpm: params.bpm.map(|param| EffectParam::new(param.disable())),
+            param_phase: params.phase.map(|param| EffectParam::new(param.disable())),
+            param_strength: params.strength.map(|param| EffectParam::new(param.disable())),
+            param_onsets: params.onsets.map(|param| EffectParam::new(param.disable())),
+            audio_beat: None,
+            properties: EffectParamCustomFFTProperties::new(identifier, settings, preprocess_result)?,
+            property_sensitivity,
+            property_bands,
+            property_band_f_min,
+            property_band_f_max,
+            property_bpm_min,
+            property_bpm_max,
+        };
+
+        result.request_audio_beat();
+
+        Ok(result)
+    }
+
+    fn beat_parameters(&self) -> BeatParameters {
+        BeatParameters {
+            bands: self.property_bands.get_value().max(1) as usize,
+            f_min: self.property_band_f_min.get_value() as f32,
+            f_max: self.property_band_f_max.get_value() as f32,
+            sensitivity: self.property_sensitivity.get_value() as f32,
+            bpm_min: self.property_bpm_min.get_value(),
+            bpm_max: self.property_bpm_max.get_value(),
+        }
+    }
+
+    /// The beat detection restarts, if its parameters were changed.
+    fn request_audio_beat(&mut self) {
+        let descriptor = GlobalStateAudioBeatDescriptor::new(self.properties.descriptor(), &self.beat_parameters());
+
+        self.audio_beat = Some(GLOBAL_STATE.request_audio_beat(&descriptor));
+    }
+
+    fn params_mut(&mut self) -> impl Iterator<Item=&mut EffectParamFloat> {
+        self.param_time.iter_mut()
+            .chain(self.param_bpm.iter_mut())
+            .chain(self.param_phase.iter_mut())
+            .chain(self.param_strength.iter_mut())
+    }
+}
+
+impl BindableProperty for EffectParamCustomBeat {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.properties.add_properties(properties);
+        self.property_sensitivity.add_properties(properties);
+        self.property_bands.add_properties(properties);
+        self.property_band_f_min.add_properties(properties);
+        self.property_band_f_max.add_properties(properties);
+        self.property_bpm_min.add_properties(properties);
+        self.property_bpm_max.add_properties(properties);
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.properties.reload_settings(settings);
+        self.property_sensitivity.reload_settings(settings);
+        self.property_bands.reload_settings(settings);
+        self.property_band_f_min.reload_settings(settings);
+        self.property_band_f_max.reload_settings(settings);
+        self.property_bpm_min.reload_settings(settings);
+        self.property_bpm_max.reload_settings(settings);
+        self.request_audio_beat();
+    }
+
+    fn prepare_values(&mut self) {
+        let state = if let Some(state) = self.audio_beat.as_ref().unwrap().retrieve_result() {
+            state
+        } else {
+            return;
+        };
+
+        if let Some(param_time) = self.param_time.as_mut() {
+            param_time.prepare_value(state.time_since_beat);
+        }
+
+        if let Some(param_count) = self.param_count.as_mut() {
+            param_count.prepare_value(state.count);
+        }
+
+        if let Some(param_bpm) = self.param_bpm.as_mut() {
+            param_bpm.prepare_value(state.bpm);
+        }
+
+        if let Some(param_phase) = self.param_phase.as_mut() {
+            param_phase.prepare_value(state.phase);
+        }
+
+        if let Some(param_strength) = self.param_strength.as_mut() {
+            param_strength.prepare_value(state.strength);
+        }
+
+        if let Some(param_onsets) = self.param_onsets.as_mut() {
+            param_onsets.prepare_value(r32f_texture(&state.band_times));
+        }
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        self.params_mut().for_each(|param| param.stage_value(graphics_context));
+
+        if let Some(param_count) = self.param_count.as_mut() {
+            param_count.stage_value(graphics_context);
+        }
+
+        if let Some(param_onsets) = self.param_onsets.as_mut() {
+            param_onsets.stage_value(graphics_context);
+        }
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        self.params_mut().for_each(|param| param.assign_value(graphics_context));
+
+        if let Some(param_count) = self.param_count.as_mut() {
+            param_count.assign_value(graphics_context);
+        }
+
+        if let Some(param_onsets) = self.param_onsets.as_mut() {
+            param_onsets.assign_value_if_staged(graphics_context);
+        }
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        let Self { param_time, param_count, param_bpm, param_phase, param_strength, param_onsets, .. } = self;
+
+        param_time.into_iter()
+            .chain(param_bpm)
+            .chain(param_phase)
+            .chain(param_strength)
+            .for_each(|param| param.enable_and_drop(graphics_context));
+
+        if let Some(param_count) = param_count {
+            param_count.enable_and_drop(graphics_context);
+        }
+
+        if let Some(param_onsets) = param_onsets {
+            param_onsets.enable_and_drop(graphics_context);
+        }
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.properties.rename_source(settings, previous_name, new_name)
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.properties.message().or_else(|| {
+            self.audio_beat.as_ref()
+                .and_then(|audio_beat| audio_beat.audio_input_error())
+                .map(Cow::Owned)
+        })
+    }
+}
+
+pub struct EffectParamCustomAudioLevel {
+    /// The RMS, peak, short-term and momentary loudness as linear amplitudes
+    pub param_linear: Option<EffectParamVec4>,
+    /// The RMS and peak in dBFS, the short-term and momentary loudness in LUFS
+    pub param_db: Option<EffectParamVec4>,
+    pub audio_level: Option<Arc<GlobalStateAudioLevel>>,
+    pub property_mix: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_audio_source: LoadedValueTypeSourceList,
+    pub property_audio_file: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>,
+    pub property_channel: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_attack: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_release: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_peak_hold: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_peak_decay: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomAudioLevel {
+    pub fn new<'a>(
+        param_linear: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeVec4>>>,
+        param_db: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeVec4>>>,
+        identifier: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property_mix = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: false,
+                default_value: 1,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: MAX_AUDIO_MIXES as i32,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("mix"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_audio_source = audio_source_property(identifier, settings, preprocess_result)?;
+        let property_audio_file = audio_file_property(identifier, settings, preprocess_result)?;
+        let property_channel = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: false,
+                default_value: 1,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 2, // FIXME: Causes crashes when `MAX_AUDIO_CHANNELS as i32` is used, supposedly fixed in next OBS release
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("channel"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_attack = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 10.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 10000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("attack"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_release = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 300.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 10000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("release"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_peak_hold = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 1000.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 10000.0,
+                    step: 1.0,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("peak_hold"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_peak_decay = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 20.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 1000.0,
+                    step: 0.1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("peak_decay"),
+            preprocess_result,
+            settings,
+        )?;
+
+        let mut result = Self {
+            param_linear: param_linear.map(|param| EffectParam::new(param.disable())),
+            param_db: param_db.map(|param| EffectParam::new(param.disable())),
+            audio_level: None,
+            property_mix,
+            property_audio_source,
+            property_audio_file,
+            property_channel,
+            property_attack,
+            property_release,
+            property_peak_hold,
+            property_peak_decay,
+        };
+
+        result.request_audio_level();
+
+        Ok(result)
+    }
+
+    fn request_audio_level(&mut self) {
+        let audio_level_descriptor = GlobalStateAudioLevelDescriptor::new(
+            audio_input_descriptor(&self.property_mix, &self.property_audio_source, &self.property_audio_file),
+            self.property_channel.get_value() as usize - 1,
+            &LevelMeterParameters {
+                attack: self.property_attack.get_value(),
+                release: self.property_release.get_value(),
+                peak_hold: self.property_peak_hold.get_value(),
+                peak_decay: self.property_peak_decay.get_value(),
+            },
+        );
+
+        self.audio_level = Some(GLOBAL_STATE.request_audio_level(&audio_level_descriptor));
+    }
+
+    fn params_mut(&mut self) -> impl Iterator<Item=&mut EffectParamVec4> {
+        self.param_linear.iter_mut().chain(self.param_db.iter_mut())
+    }
+}
+
+impl BindableProperty for EffectParamCustomAudioLevel {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.property_mix.add_properties(properties);
+        self.property_audio_source.add_properties(properties);
+        self.property_audio_file.add_properties(properties);
+        self.property_channel.add_properties(properties);
+        self.property_attack.add_properties(properties);
+        self.property_release.add_properties(properties);
+        self.property_peak_hold.add_properties(properties);
+        self.property_peak_decay.add_properties(properties);
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.property_mix.reload_settings(settings);
+        self.property_audio_source.reload_settings(settings);
+        self.property_audio_file.reload_settings(settings);
+        self.property_channel.reload_settings(settings);
+        self.property_attack.reload_settings(settings);
+        self.property_release.reload_settings(settings);
+        self.property_peak_hold.reload_settings(settings);
+        self.property_peak_decay.reload_settings(settings);
+        self.request_audio_level();
+    }
+
+    fn prepare_values(&mut self) {
+        let levels = self.audio_level.as_ref().unwrap().retrieve_result().unwrap_or_default();
+
+        if let Some(param_linear) = self.param_linear.as_mut() {
+            param_linear.prepare_value([
+                levels.rms,
+                levels.peak,
+                loudness::db_to_amplitude(levels.short_term_loudness),
+                loudness::db_to_amplitude(levels.momentary_loudness),
+            ]);
+        }
+
+        if let Some(param_db) = self.param_db.as_mut() {
+            param_db.prepare_value([
+                loudness::amplitude_to_db(levels.rms),
+                loudness::amplitude_to_db(levels.peak),
+                levels.short_term_loudness,
+                levels.momentary_loudness,
+            ]);
+        }
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        self.params_mut().for_each(|param| param.stage_value(graphics_context));
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        self.params_mut().for_each(|param| param.assign_value(graphics_context));
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        self.param_linear.into_iter()
+            .chain(self.param_db)
+            .for_each(|param| param.enable_and_drop(graphics_context));
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.property_audio_source.rename_source(settings, previous_name, new_name)
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.audio_level.as_ref()
+            .and_then(|audio_level| audio_level.audio_input_error())
+            .map(Cow::Owned)
+    }
+}
+
+pub struct EffectParamCustomWaveform {
+    pub effect_param: EffectParamTexture,
+    pub effect_param_previous: Option<EffectParamTexture>,
+    pub audio_waveform: Option<Arc<GlobalStateAudioWaveform>>,
+    pub property_mix: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_audio_source: LoadedValueTypeSourceList,
+    pub property_audio_file: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorPath>,
+    pub property_channel: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_length: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorI32>,
+    pub property_gain: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorF64>,
+    pub property_trigger: LoadedValueTypeProperty<LoadedValueTypePropertyDescriptorBool>,
+}
+
+// Does not implement EffectParamCustom because of different argument requirements
+impl EffectParamCustomWaveform {
+    pub fn new<'a>(
+        param: GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>,
+        param_previous: Option<GraphicsContextDependentEnabled<'a, GraphicsEffectParamTyped<ShaderParamTypeTexture>>>,
+        identifier: &str,
+        settings: &mut SettingsContext,
+        preprocess_result: &PreprocessResult,
+    ) -> Result<Self, Cow<'static, str>> {
+        let property_mix = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: false,
+                default_value: 1,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: MAX_AUDIO_MIXES as i32,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("mix"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_audio_source = audio_source_property(identifier, settings, preprocess_result)?;
+        let property_audio_file = audio_file_property(identifier, settings, preprocess_result)?;
+        let property_channel = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: false,
+                default_value: 1,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 2, // FIXME: Causes crashes when `MAX_AUDIO_CHANNELS as i32` is used, supposedly fixed in next OBS release
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("channel"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_length = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 1024,
+                default_descriptor_specialization: PropertyDescriptorSpecializationI32 {
+                    min: 1,
+                    max: 16384,
+                    step: 1,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("length"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_gain = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: 1.0,
+                default_descriptor_specialization: PropertyDescriptorSpecializationF64 {
+                    min: 0.0,
+                    max: 100.0,
+                    step: 0.01,
+                    slider: false,
+                },
+            },
+            identifier,
+            Some("gain"),
+            preprocess_result,
+            settings,
+        )?;
+        let property_trigger = <LoadedValueTypeProperty<_> as LoadedValueType>::from(
+            LoadedValueTypePropertyArgs {
+                allow_definitions_in_source: true,
+                default_value: false,
+                default_descriptor_specialization: PropertyDescriptorSpecializationBool {},
+            },
+            identifier,
+            Some("trigger"),
+            preprocess_result,
+            settings,
+        )?;
+
+        let mut result = Self {
+            effect_param: EffectParam::new(param.disable()),
+            effect_param_previous: param_previous.map(|param_previous| EffectParam::new(param_previous.disable())),
+            audio_waveform: None,
+            property_mix,
+            property_audio_source,
+            property_audio_file,
+            property_channel,
+            property_length,
+            property_gain,
+            property_trigger,
+        };
+
+        result.request_audio_waveform();
+
+        Ok(result)
+    }
+
+    fn request_audio_waveform(&mut self) {
+        let audio_waveform_descriptor = GlobalStateAudioWaveformDescriptor::new(
+            audio_input_descriptor(&self.property_mix, &self.property_audio_source, &self.property_audio_file),
+            self.property_channel.get_value() as usize - 1,
+            self.property_length.get_value().max(1) as usize,
+        );
+
+        self.audio_waveform = Some(GLOBAL_STATE.request_audio_waveform(&audio_waveform_descriptor));
+    }
+}
+
+impl BindableProperty for EffectParamCustomWaveform {
+    fn add_properties(&self, properties: &mut Properties) {
+        self.property_mix.add_properties(properties);
+        self.property_audio_source.add_properties(properties);
+        self.property_audio_file.add_properties(properties);
+        self.property_channel.add_properties(properties);
+        self.property_length.add_properties(properties);
+        self.property_gain.add_properties(properties);
+        self.property_trigger.add_properties(properties);
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.property_mix.reload_settings(settings);
+        self.property_audio_source.reload_settings(settings);
+        self.property_audio_file.reload_settings(settings);
+        self.property_channel.reload_settings(settings);
+        self.property_length.reload_settings(settings);
+        self.property_gain.reload_settings(settings);
+        self.property_trigger.reload_settings(settings);
+        self.request_audio_waveform();
+    }
+
+    fn prepare_values(&mut self) {
+        let samples = if let Some(samples) = self.audio_waveform.as_ref().unwrap().retrieve_result() {
+            samples
+        } else {
+            return;
+        };
+
+        let length = self.property_length.get_value().max(1) as usize;
+        let start = if self.property_trigger.get_value() {
+            find_waveform_trigger(&samples, length)
+        } else {
+            samples.len().saturating_sub(length)
+        };
+        let gain = self.property_gain.get_value() as f32;
+        // Pad with silence until enough samples are captured
+        let mut waveform = vec![0.0; length];
+
+        for (target, sample) in waveform.iter_mut().zip(&samples[start..]) {
+            *target = sample * gain;
+        }
+
+        let texture_data = unsafe {
+            std::slice::from_raw_parts::<u8>(
+                waveform.as_ptr() as *const _,
+                waveform.len() * std::mem::size_of::<f32>(),
+            )
+        }.iter().copied().collect::<Vec<_>>();
+
+        let texture_waveform = TextureDescriptor {
+            dimensions: [waveform.len(), 1],
+            color_format: ColorFormatKind::R32F,
+            levels: smallvec![texture_data],
+            flags: 0,
+        };
+
+        self.effect_param.prepare_value(texture_waveform);
+    }
+
+    fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext) {
+        if let Some(effect_param_previous) = self.effect_param_previous.as_mut() {
+            if let Some(previous_texture_waveform) = self.effect_param.take_staged_value() {
+                effect_param_previous.stage_value_custom(previous_texture_waveform, graphics_context);
+            }
+        }
+
+        self.effect_param.stage_value(graphics_context);
+    }
+
+    fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext) {
+        if let Some(effect_param_previous) = self.effect_param_previous.as_mut() {
+            effect_param_previous.assign_value_if_staged(graphics_context);
+        }
+        self.effect_param.assign_value_if_staged(graphics_context);
+    }
+
+    fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        if let Some(effect_param_previous) = self.effect_param_previous {
+            effect_param_previous.enable_and_drop(graphics_context);
+        }
+        self.effect_param.enable_and_drop(graphics_context);
+    }
+
+    fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.property_audio_source.rename_source(settings, previous_name, new_name)
+    }
+
+    fn message(&self) -> Option<Cow<str>> {
+        self.audio_waveform.as_ref()
+            .and_then(|audio_waveform| audio_waveform.audio_input_error())
+            .map(Cow::Owned)
     }
 }
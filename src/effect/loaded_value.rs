@@ -2,7 +2,8 @@ use std::fmt::Debug;
 use std::str::FromStr;
 use std::borrow::Cow;
 use std::ffi::CString;
-use obs_wrapper::source::*;
+use std::os::raw::c_char;
+use obs_wrapper::{obs_sys::*, source::*};
 use crate::*;
 
 pub trait LoadedValueType: Sized {
@@ -48,7 +49,7 @@ pub trait LoadedValueType: Sized {
 }
 
 pub struct LoadedValueTypeSourceArgs<T> where T: FromStr + Clone {
-    default_value: Option<T>,
+    pub default_value: Option<T>,
 }
 
 /// A loaded value of which the value can only be specified in the shader source code.
@@ -439,6 +440,52 @@ impl LoadedValueTypePropertyDescriptor for LoadedValueTypePropertyDescriptorBool
     }
 }
 
+#[derive(Debug)]
+pub struct LoadedValueTypePropertyDescriptorPath {
+    descriptor: PropertyDescriptor<PropertyDescriptorSpecializationPath>,
+    description: LoadedValueTypeSource<String>,
+}
+
+impl LoadedValueTypePropertyDescriptor for LoadedValueTypePropertyDescriptorPath {
+    type Specialization = PropertyDescriptorSpecializationPath;
+
+    fn from_identifier(
+        args: LoadedValueTypePropertyDescriptorArgs<Self::Specialization>,
+        identifier: &str,
+        preprocess_result: &PreprocessResult,
+        settings: &mut SettingsContext,
+    ) -> Result<Self, Cow<'static, str>> {
+        let description = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(identifier.to_string()),
+            },
+            identifier,
+            Some("description"),
+            preprocess_result,
+            settings,
+        )?;
+        let descriptor = PropertyDescriptor {
+            name: CString::new(identifier).unwrap(),
+            description: CString::new(description.get_value().unwrap()).unwrap(),
+            specialization: args.default_value,
+        };
+
+        Ok(Self {
+            descriptor,
+            description,
+        })
+    }
+
+    fn add_properties(&self, properties: &mut Properties) {
+        self.description.add_properties(properties);
+        properties.add_property(&self.descriptor);
+    }
+
+    fn get_value(&self) -> PropertyDescriptor<Self::Specialization> {
+        self.descriptor.clone()
+    }
+}
+
 pub trait LoadedValueTypePropertyBounds = LoadedValueTypePropertyDescriptor<Specialization: ValuePropertyDescriptorSpecialization<ValueType: FromStr + Clone>> + Debug;
 
 pub struct LoadedValueTypePropertyArgs<T: LoadedValueTypePropertyBounds> {
@@ -555,3 +602,320 @@ impl<T: LoadedValueTypePropertyBounds> LoadedValueType for LoadedValueTypeProper
         self.value.clone()
     }
 }
+
+/// A list property with a fixed set of string items.
+/// The value is the selected item, so it can be read using a
+/// `PropertyDescriptorSpecializationString` descriptor of the same name.
+#[derive(Clone, Debug)]
+pub struct PropertyDescriptorSpecializationList {
+    /// `(value, label)` pairs
+    pub items: &'static [(&'static str, &'static str)],
+}
+
+impl PropertyDescriptorSpecialization for PropertyDescriptorSpecializationList {
+    unsafe fn create_property(
+        &self,
+        name: *const c_char,
+        description: *const c_char,
+        properties: *mut obs_properties_t,
+    ) -> *mut obs_property_t {
+        let property = obs_properties_add_list(
+            properties,
+            name,
+            description,
+            obs_combo_type_OBS_COMBO_TYPE_LIST,
+            obs_combo_format_OBS_COMBO_FORMAT_STRING,
+        );
+
+        for (value, label) in self.items {
+            let value = CString::new(*value).unwrap();
+            let label = CString::new(*label).unwrap();
+
+            obs_property_list_add_string(property, label.as_ptr(), value.as_ptr());
+        }
+
+        property
+    }
+}
+
+pub struct LoadedValueTypeListArgs {
+    pub default_value: &'static str,
+    pub items: &'static [(&'static str, &'static str)],
+}
+
+/// A hierarchically-loaded value, which is one of a fixed set of items.
+/// If the value is not specified in the shader source code, it is picked from a list in the UI.
+pub struct LoadedValueTypeList {
+    /// Used to read the selected item from the settings, `None` if the value is hardcoded
+    descriptor: Option<PropertyDescriptor<PropertyDescriptorSpecializationString>>,
+    loaded_value_description: Option<LoadedValueTypeSource<String>>,
+    loaded_value_default: Option<LoadedValueTypeSource<String>>,
+    items: &'static [(&'static str, &'static str)],
+    default_value: String,
+    value: String,
+}
+
+impl LoadedValueTypeList {
+    fn is_item(items: &[(&str, &str)], value: &str) -> bool {
+        items.iter().any(|(item, _)| *item == value)
+    }
+
+    fn invalid_value_message(identifier: &str, value: &str, items: &[(&str, &str)]) -> String {
+        format!(
+            "Invalid value `{}` of property `{}`, expected one of: {}.",
+            value,
+            identifier,
+            items.iter().map(|(item, _)| format!("`{}`", item)).collect::<Vec<_>>().join(", "),
+        )
+    }
+
+    fn load_value(&mut self, settings: &mut SettingsContext) {
+        if let Some(descriptor) = self.descriptor.as_ref() {
+            let default_value = CString::new(self.default_value.as_str()).unwrap();
+            let value = settings.get_property_value(descriptor, &default_value)
+                .to_string_lossy()
+                .into_owned();
+
+            // Fall back to the default value, if the settings contain an item that no longer exists
+            self.value = if Self::is_item(self.items, &value) {
+                value
+            } else {
+                self.default_value.clone()
+            };
+        }
+    }
+}
+
+impl LoadedValueType for LoadedValueTypeList {
+    type Output = String;
+    type Args = LoadedValueTypeListArgs;
+
+    fn from_identifier(
+        args: Self::Args,
+        identifier: &str,
+        preprocess_result: &PreprocessResult,
+        settings: &mut SettingsContext,
+    ) -> Result<Self, Cow<'static, str>> {
+        if let Some(value) = preprocess_result.parse::<String>(identifier).transpose()? {
+            if !Self::is_item(args.items, &value) {
+                throw!(Self::invalid_value_message(identifier, &value, args.items));
+            }
+
+            return Ok(Self {
+                descriptor: None,
+                loaded_value_description: None,
+                loaded_value_default: None,
+                items: args.items,
+                default_value: value.clone(),
+                value,
+            });
+        }
+
+        let loaded_value_default = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(args.default_value.to_string()),
+            },
+            identifier,
+            Some("default"),
+            preprocess_result,
+            settings,
+        )?;
+        let default_value = loaded_value_default.get_value().unwrap();
+
+        if !Self::is_item(args.items, &default_value) {
+            throw!(Self::invalid_value_message(&format!("{}__default", identifier), &default_value, args.items));
+        }
+
+        let loaded_value_description = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(identifier.to_string()),
+            },
+            identifier,
+            Some("description"),
+            preprocess_result,
+            settings,
+        )?;
+        let descriptor = PropertyDescriptor {
+            name: CString::new(identifier).unwrap(),
+            description: CString::new(loaded_value_description.get_value().unwrap()).unwrap(),
+            specialization: PropertyDescriptorSpecializationString {
+                string_type: StringType::Default,
+            },
+        };
+        let mut result = Self {
+            descriptor: Some(descriptor),
+            loaded_value_description: Some(loaded_value_description),
+            loaded_value_default: Some(loaded_value_default),
+            items: args.items,
+            value: default_value.clone(),
+            default_value,
+        };
+
+        result.load_value(settings);
+
+        Ok(result)
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.load_value(settings);
+    }
+
+    fn add_properties(&self, properties: &mut Properties) {
+        if let Some(loaded_value_default) = self.loaded_value_default.as_ref() {
+            loaded_value_default.add_properties(properties);
+        }
+
+        if let Some(loaded_value_description) = self.loaded_value_description.as_ref() {
+            loaded_value_description.add_properties(properties);
+        }
+
+        if let Some(descriptor) = self.descriptor.as_ref() {
+            properties.add_property(&PropertyDescriptor {
+                name: descriptor.name.clone(),
+                description: descriptor.description.clone(),
+                specialization: PropertyDescriptorSpecializationList {
+                    items: self.items,
+                },
+            });
+        }
+    }
+
+    fn get_value(&self) -> Self::Output {
+        self.value.clone()
+    }
+}
+
+pub struct LoadedValueTypeSourceListArgs {
+    /// Only sources with any of these output flags are listed, e.g. `OBS_SOURCE_AUDIO`
+    pub output_flags: u32,
+}
+
+/// A hierarchically-loaded name of a source, which is empty if no source is selected.
+/// If the value is not specified in the shader source code, it is picked from a list in the UI.
+pub struct LoadedValueTypeSourceList {
+    /// Used to read the selected source from the settings, `None` if the value is hardcoded
+    descriptor: Option<PropertyDescriptor<PropertyDescriptorSpecializationString>>,
+    loaded_value_description: Option<LoadedValueTypeSource<String>>,
+    loaded_value_default: Option<LoadedValueTypeSource<String>>,
+    output_flags: u32,
+    default_value: String,
+    value: String,
+}
+
+impl LoadedValueTypeSourceList {
+    fn load_value(&mut self, settings: &mut SettingsContext) {
+        if let Some(descriptor) = self.descriptor.as_ref() {
+            let default_value = CString::new(self.default_value.as_str()).unwrap();
+
+            self.value = settings.get_property_value(descriptor, &default_value)
+                .to_string_lossy()
+                .into_owned();
+        }
+    }
+
+    /// Stores the new name in the settings, if the renamed source is selected, so that it stays selected
+    /// after a reload. Names specified in the shader source code cannot be updated.
+    /// Returns `true`, if the settings were changed.
+    pub fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        if let Some(descriptor) = self.descriptor.as_ref() {
+            if self.value == previous_name {
+                settings.set_property_value(descriptor, CString::new(new_name).unwrap_or_default());
+                self.value = new_name.to_string();
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl LoadedValueType for LoadedValueTypeSourceList {
+    type Output = String;
+    type Args = LoadedValueTypeSourceListArgs;
+
+    fn from_identifier(
+        args: Self::Args,
+        identifier: &str,
+        preprocess_result: &PreprocessResult,
+        settings: &mut SettingsContext,
+    ) -> Result<Self, Cow<'static, str>> {
+        if let Some(value) = preprocess_result.parse::<String>(identifier).transpose()? {
+            return Ok(Self {
+                descriptor: None,
+                loaded_value_description: None,
+                loaded_value_default: None,
+                output_flags: args.output_flags,
+                default_value: value.clone(),
+                value,
+            });
+        }
+
+        let loaded_value_default = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(String::new()),
+            },
+            identifier,
+            Some("default"),
+            preprocess_result,
+            settings,
+        )?;
+        let default_value = loaded_value_default.get_value().unwrap();
+        let loaded_value_description = <LoadedValueTypeSource::<String> as LoadedValueType>::from(
+            LoadedValueTypeSourceArgs {
+                default_value: Some(identifier.to_string()),
+            },
+            identifier,
+            Some("description"),
+            preprocess_result,
+            settings,
+        )?;
+        let descriptor = PropertyDescriptor {
+            name: CString::new(identifier).unwrap(),
+            description: CString::new(loaded_value_description.get_value().unwrap()).unwrap(),
+            specialization: PropertyDescriptorSpecializationString {
+                string_type: StringType::Default,
+            },
+        };
+        let mut result = Self {
+            descriptor: Some(descriptor),
+            loaded_value_description: Some(loaded_value_description),
+            loaded_value_default: Some(loaded_value_default),
+            output_flags: args.output_flags,
+            value: default_value.clone(),
+            default_value,
+        };
+
+        result.load_value(settings);
+
+        Ok(result)
+    }
+
+    fn reload_settings(&mut self, settings: &mut SettingsContext) {
+        self.load_value(settings);
+    }
+
+    fn add_properties(&self, properties: &mut Properties) {
+        if let Some(loaded_value_default) = self.loaded_value_default.as_ref() {
+            loaded_value_default.add_properties(properties);
+        }
+
+        if let Some(loaded_value_description) = self.loaded_value_description.as_ref() {
+            loaded_value_description.add_properties(properties);
+        }
+
+        if let Some(descriptor) = self.descriptor.as_ref() {
+            properties.add_property(&PropertyDescriptor {
+                name: descriptor.name.clone(),
+                description: descriptor.description.clone(),
+                specialization: PropertyDescriptorSpecializationSourceList {
+                    output_flags: self.output_flags,
+                    selected: CString::new(self.value.as_str()).ok(),
+                },
+            });
+        }
+    }
+
+    fn get_value(&self) -> Self::Output {
+        self.value.clone()
+    }
+}
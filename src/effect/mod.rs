@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ffi::CString;
 use obs_wrapper::{
     graphics::*,
@@ -11,9 +11,15 @@ use crate::*;
 
 mod effect_param;
 mod loaded_value;
+mod ring_texture;
+mod render_pass;
+mod source_texture;
 
 pub use effect_param::*;
 pub use loaded_value::*;
+pub use ring_texture::*;
+pub use render_pass::*;
+pub use source_texture::*;
 
 /// An object representing a binding of setting-properties to graphics uniforms.
 pub trait BindableProperty: Downcast {
@@ -21,8 +27,23 @@ pub trait BindableProperty: Downcast {
     fn reload_settings(&mut self, settings: &mut SettingsContext);
     fn prepare_values(&mut self);
     fn stage_value<'a>(&mut self, graphics_context: &'a GraphicsContext);
+
+    /// Called every frame before the effect is rendered, to render any textures the value depends on.
+    fn render(&mut self, _graphics_context: &GraphicsContext) {}
+
     fn assign_value<'a>(&mut self, graphics_context: &'a FilterContext);
     fn enable_and_drop(self, graphics_context: &GraphicsContext);
+
+    /// Called when a source is renamed, so that settings referring to the source by its name can follow it.
+    /// Returns `true`, if the settings were changed.
+    fn rename_source(&mut self, _settings: &mut SettingsContext, _previous_name: &str, _new_name: &str) -> bool {
+        false
+    }
+
+    /// A problem with the current value to be displayed to the user, if there is one.
+    fn message(&self) -> Option<Cow<str>> {
+        None
+    }
 }
 impl_downcast!(BindableProperty);
 
@@ -35,6 +56,7 @@ pub struct EffectParamsCustom {
 impl EffectParamsCustom {
     pub fn from<'a>(
         mut params: HashMap<String, Indexed<GraphicsContextDependentEnabled<'a, GraphicsEffectParam>>>,
+        shader_directory: &Path,
         settings: &mut SettingsContext,
         preprocess_result: &PreprocessResult,
     ) -> Result<Self, Cow<'static, str>> {
@@ -44,25 +66,77 @@ impl EffectParamsCustom {
 
         let result: Result<(), Cow<'static, str>> = try {
             {
-                let pattern_builtin_texture_fft = Regex::new(r"^builtin_texture_fft_(?P<field>\w+)$").unwrap();
+                let pattern_builtin_texture_audio = Regex::new(r"^builtin_texture_(?P<kind>fft|mfcc|chroma|waveform)_(?P<field>\w+)$").unwrap();
                 let pattern_field_previous = Regex::new(r"^.*_previous$").unwrap();
+                let pattern_field_metadata = Regex::new(r"^(?P<base>.*)_(size|sample_rate|frequency_range|mode)$").unwrap();
                 let param_names = params.keys().cloned().collect::<Vec<_>>();
 
                 for param_name in &param_names {
-                    let captures = if let Some(captures) = pattern_builtin_texture_fft.captures(&param_name) {
+                    let captures = if let Some(captures) = pattern_builtin_texture_audio.captures(&param_name) {
                         captures
                     } else {
                         continue;
                     };
                     let field_name = captures.name("field").unwrap().as_str();
+                    let kind = captures.name("kind").unwrap().as_str();
 
                     if pattern_field_previous.is_match(&field_name) {
                         continue;
                     }
 
+                    let param_type = if let Some(param) = params.get(param_name) {
+                        param.inner.param_type()
+                    } else {
+                        // Already bound as a companion uniform variable of another FFT texture
+                        continue;
+                    };
+
+                    // The frequency mapping of FFT textures is described by companion uniform variables,
+                    // which are bound along with their texture
+                    if kind == "fft" && param_type != Texture {
+                        let base_param_type = pattern_field_metadata.captures(&param_name)
+                            .and_then(|captures| params.get(captures.name("base").unwrap().as_str()))
+                            .map(|base_param| base_param.inner.param_type());
+
+                        if base_param_type == Some(Texture) {
+                            continue;
+                        }
+                    }
+
                     let (param_index, param) = params.remove(param_name).unwrap().into_tuple();
                     let param_previous = params.remove(&format!("{}_previous", param_name))
                         .map(|indexed| indexed.into_inner());
+                    let mut metadata_params = EffectParamCustomFFTMetadataParams::default();
+
+                    if kind == "fft" {
+                        for (suffix, expected_type) in &[("size", Int), ("sample_rate", Float), ("frequency_range", Vec2), ("mode", Int)] {
+                            let metadata_param_name = format!("{}_{}", param_name, suffix);
+
+                            // A texture of this name is an FFT texture of its own
+                            match params.get(&metadata_param_name) {
+                                Some(metadata_param) if metadata_param.inner.param_type() != Texture => (),
+                                _ => continue,
+                            }
+
+                            let metadata_param = params.remove(&metadata_param_name).unwrap().into_inner();
+
+                            if metadata_param.param_type() != *expected_type {
+                                throw!(format!(
+                                    "Builtin field `{}_{}` must be of type `{}`",
+                                    field_name,
+                                    suffix,
+                                    match expected_type { Int => "int", Float => "float", _ => "float2" },
+                                ));
+                            }
+
+                            match *suffix {
+                                "size" => metadata_params.size = Some(metadata_param.downcast().unwrap()),
+                                "sample_rate" => metadata_params.sample_rate = Some(metadata_param.downcast().unwrap()),
+                                "frequency_range" => metadata_params.frequency_range = Some(metadata_param.downcast().unwrap()),
+                                _ => metadata_params.mode = Some(metadata_param.downcast().unwrap()),
+                            }
+                        }
+                    }
 
                     if param.param_type() != Texture {
                         throw!(format!("Builtin field `{}` must be of type `{}`", field_name, "texture2d"));
@@ -74,12 +148,61 @@ impl EffectParamsCustom {
                         }
                     }
 
+                    let param = param.downcast().unwrap();
+                    let param_previous = param_previous.map(|param_previous| param_previous.downcast().unwrap());
+                    let bindable: Box<dyn BindableProperty> = match kind {
+                        "fft" => Box::new(EffectParamCustomFFT::new(FFTTextureKind::Spectrum, param, param_previous, metadata_params, field_name, settings, preprocess_result)?),
+                        "mfcc" => Box::new(EffectParamCustomFFT::new(FFTTextureKind::Mfcc, param, param_previous, metadata_params, field_name, settings, preprocess_result)?),
+                        "chroma" => Box::new(EffectParamCustomFFT::new(FFTTextureKind::Chroma, param, param_previous, metadata_params, field_name, settings, preprocess_result)?),
+                        _ => Box::new(EffectParamCustomWaveform::new(param, param_previous, field_name, settings, preprocess_result)?),
+                    };
+
                     bound_params.push(
                         Indexed {
                             index: param_index,
-                            inner: Box::new(EffectParamCustomFFT::new(
+                            inner: bindable,
+                        },
+                    );
+                }
+            }
+
+            {
+                let pattern_builtin_texture_spectrogram = Regex::new(r"^builtin_texture_spectrogram_(?P<field>\w+)$").unwrap();
+                let pattern_field_head = Regex::new(r"^.*_head$").unwrap();
+                let param_names = params.keys().cloned().collect::<Vec<_>>();
+
+                for param_name in &param_names {
+                    let captures = if let Some(captures) = pattern_builtin_texture_spectrogram.captures(&param_name) {
+                        captures
+                    } else {
+                        continue;
+                    };
+                    let field_name = captures.name("field").unwrap().as_str();
+
+                    if pattern_field_head.is_match(&field_name) {
+                        continue;
+                    }
+
+                    let (param_index, param) = params.remove(param_name).unwrap().into_tuple();
+                    let param_head = params.remove(&format!("{}_head", param_name))
+                        .map(|indexed| indexed.into_inner());
+
+                    if param.param_type() != Texture {
+                        throw!(format!("Builtin field `{}` must be of type `{}`", field_name, "texture2d"));
+                    }
+
+                    if let Some(ref param_head) = param_head.as_ref() {
+                        if param_head.param_type() != Int {
+                            throw!(format!("Builtin field `{}_head` must be of type `{}`", field_name, "int"));
+                        }
+                    }
+
+                    bound_params.push(
+                        Indexed {
+                            index: param_index,
+                            inner: Box::new(EffectParamCustomSpectrogram::new(
                                 param.downcast().unwrap(),
-                                param_previous.map(|param_previous| param_previous.downcast().unwrap()),
+                                param_head.map(|param_head| param_head.downcast().unwrap()),
                                 field_name,
                                 settings,
                                 preprocess_result,
@@ -88,12 +211,131 @@ impl EffectParamsCustom {
                     );
                 }
             }
+
+            {
+                let pattern_builtin_beat = Regex::new(r"^builtin_beat_(?P<field>\w+)_(?P<value>time|count|bpm|phase|strength|onsets)$").unwrap();
+                let param_names = params.keys().cloned().collect::<Vec<_>>();
+                let mut beats: HashMap<std::string::String, (usize, EffectParamCustomBeatParams)> = HashMap::new();
+
+                for param_name in &param_names {
+                    let captures = if let Some(captures) = pattern_builtin_beat.captures(&param_name) {
+                        captures
+                    } else {
+                        continue;
+                    };
+                    let field_name = captures.name("field").unwrap().as_str();
+                    let value_name = captures.name("value").unwrap().as_str();
+                    let (param_index, param) = params.remove(param_name).unwrap().into_tuple();
+                    let (expected_type, expected_type_name) = match value_name {
+                        "count" => (Int, "int"),
+                        "onsets" => (Texture, "texture2d"),
+                        _ => (Float, "float"),
+                    };
+
+                    if param.param_type() != expected_type {
+                        throw!(format!(
+                            "Builtin field `{}_{}` must be of type `{}`",
+                            field_name,
+                            value_name,
+                            expected_type_name,
+                        ));
+                    }
+
+                    let (index, beat_params) = beats.entry(field_name.to_string())
+                        .or_insert_with(|| (param_index, Default::default()));
+
+                    // The beat detection is ordered by its first uniform variable
+                    *index = (*index).min(param_index);
+
+                    match value_name {
+                        "time" => beat_params.time = Some(param.downcast().unwrap()),
+                        "count" => beat_params.count = Some(param.downcast().unwrap()),
+                        "bpm" => beat_params.bpm = Some(param.downcast().unwrap()),
+                        "phase" => beat_params.phase = Some(param.downcast().unwrap()),
+                        "onsets" => beat_params.onsets = Some(param.downcast().unwrap()),
+                        _ => beat_params.strength = Some(param.downcast().unwrap()),
+                    }
+                }
+
+                for (field_name, (param_index, beat_params)) in beats {
+                    bound_params.push(
+                        Indexed {
+                            index: param_index,
+                            inner: Box::new(EffectParamCustomBeat::new(
+                                beat_params,
+                                &field_name,
+                                settings,
+                                preprocess_result,
+                            )?),
+                        },
+                    );
+                }
+            }
+
+            {
+                let pattern_builtin_audio_level = Regex::new(r"^builtin_audio_level_(?P<field>\w+?)(?P<db>_db)?$").unwrap();
+                let param_names = params.keys().cloned().collect::<Vec<_>>();
+                let mut audio_levels: HashMap<std::string::String, (usize, Option<_>, Option<_>)> = HashMap::new();
+
+                for param_name in &param_names {
+                    let captures = if let Some(captures) = pattern_builtin_audio_level.captures(&param_name) {
+                        captures
+                    } else {
+                        continue;
+                    };
+                    let field_name = captures.name("field").unwrap().as_str();
+                    let (param_index, param) = params.remove(param_name).unwrap().into_tuple();
+
+                    if param.param_type() != Vec4 {
+                        throw!(format!("Builtin field `{}` must be of type `{}`", param_name, "float4"));
+                    }
+
+                    let (index, param_linear, param_db) = audio_levels.entry(field_name.to_string())
+                        .or_insert_with(|| (param_index, None, None));
+
+                    *index = (*index).min(param_index);
+
+                    if captures.name("db").is_some() {
+                        *param_db = Some(param.downcast().unwrap());
+                    } else {
+                        *param_linear = Some(param.downcast().unwrap());
+                    }
+                }
+
+                for (field_name, (param_index, param_linear, param_db)) in audio_levels {
+                    bound_params.push(
+                        Indexed {
+                            index: param_index,
+                            inner: Box::new(EffectParamCustomAudioLevel::new(
+                                param_linear,
+                                param_db,
+                                &field_name,
+                                settings,
+                                preprocess_result,
+                            )?),
+                        },
+                    );
+                }
+            }
         };
 
         result.map_err(|err| {
             Cow::Owned(format!("An error occurred while binding effect uniform variable: {}", err))
         })?;
 
+        // Sizes of source textures are bound along with the texture uniforms they belong to.
+        let pattern_builtin_uv_size = Regex::new(r"^builtin_uv_size_(?P<field>\w+)$").unwrap();
+        let mut size_params = HashMap::new();
+
+        for param_name in params.keys().cloned().collect::<Vec<_>>() {
+            if let Some(captures) = pattern_builtin_uv_size.captures(&param_name) {
+                let field_name = captures.name("field").unwrap().as_str().to_string();
+                let param = params.remove(&param_name).unwrap().into_inner();
+
+                size_params.insert(field_name, param);
+            }
+        }
+
         let pattern_param_builtin = Regex::new(r"^builtin_").unwrap();
 
         for (_index, param) in params {
@@ -104,12 +346,19 @@ impl EffectParamsCustom {
                 throw!(format!("Unrecognized `builtin_` uniform variable type: {}", &param_name));
             }
 
-            Self::add_param(&mut bound_params, param, &param_name, settings, preprocess_result)
+            Self::add_param(&mut bound_params, param, &param_name, shader_directory, &mut size_params, settings, preprocess_result)
                 .map_err(|err| {
                     Cow::Owned(format!("An error occurred while binding effect uniform variable `{}`: {}", param_name, err))
                 })?;
         }
 
+        if let Some(field_name) = size_params.keys().next() {
+            throw!(format!(
+                "Builtin uniform variable `builtin_uv_size_{0}` requires a `texture2d {0}` uniform variable with a `{0}__source` property.",
+                field_name,
+            ));
+        }
+
         // Ensure the properties are stored in the order they were declared
         bound_params.sort_unstable();
 
@@ -124,6 +373,8 @@ impl EffectParamsCustom {
         bound_params: &mut Vec<Indexed<Box<dyn BindableProperty>>>,
         param: Indexed<GraphicsContextDependentEnabled<'a, GraphicsEffectParam>>,
         param_name: &str,
+        shader_directory: &Path,
+        size_params: &mut HashMap<String, GraphicsContextDependentEnabled<'a, GraphicsEffectParam>>,
         settings: &mut SettingsContext,
         preprocess_result: &PreprocessResult,
     ) -> Result<(), Cow<'static, str>> {
@@ -147,11 +398,53 @@ impl EffectParamsCustom {
                 EffectParamCustomColor::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
                     .map(|param| Box::new(param) as Box<dyn BindableProperty>)
             }).transpose()?,
-            Vec2 | Vec3 | IVec2 | IVec3 | IVec4 | Mat4 => {
-                throw!("Multi-component types as effect params are not yet supported.");
-            },
+            Vec2  => param.map(|param| {
+                EffectParamCustomVec2::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
+                    .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+            }).transpose()?,
+            Vec3  => param.map(|param| {
+                EffectParamCustomVec3::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
+                    .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+            }).transpose()?,
+            IVec2  => param.map(|param| {
+                EffectParamCustomIVec2::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
+                    .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+            }).transpose()?,
+            IVec3  => param.map(|param| {
+                EffectParamCustomIVec3::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
+                    .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+            }).transpose()?,
+            IVec4  => param.map(|param| {
+                EffectParamCustomIVec4::new(param.downcast().unwrap(), &param_name, settings, preprocess_result)
+                    .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+            }).transpose()?,
+            Mat4 => throw!("Matrices as effect params are not yet supported."),
             String => throw!("Strings as effect params are not yet supported."),
-            Texture => throw!("Textures as effect params are not yet supported."),
+            // Textures are either rendered from another source or loaded from an image file.
+            Texture => match preprocess_result.parse::<std::string::String>(&format!("{}__source", param_name)) {
+                Some(default_source) => {
+                    let default_source = default_source?;
+                    let size_param = match size_params.remove(param_name) {
+                        Some(size_param) => {
+                            if size_param.param_type() != IVec2 {
+                                throw!(format!("Builtin field `builtin_uv_size_{}` must be of type `{}`", param_name, "int2"));
+                            }
+
+                            Some(size_param.downcast().unwrap())
+                        },
+                        None => None,
+                    };
+
+                    param.map(|param| {
+                        EffectParamCustomSource::new(param.downcast().unwrap(), size_param, &param_name, &default_source, settings, preprocess_result)
+                            .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+                    }).transpose()?
+                },
+                None => param.map(|param| {
+                    EffectParamCustomImage::new(param.downcast().unwrap(), &param_name, shader_directory, settings, preprocess_result)
+                        .map(|param| Box::new(param) as Box<dyn BindableProperty>)
+                }).transpose()?,
+            },
         };
 
         bound_params.push(bindable);
@@ -171,10 +464,20 @@ impl EffectParamsCustom {
         self.params.iter_mut().for_each(|param| param.stage_value(graphics_context));
     }
 
+    pub fn render(&mut self, graphics_context: &GraphicsContext) {
+        self.params.iter_mut().for_each(|param| param.render(graphics_context));
+    }
+
     pub fn assign_values(&mut self, graphics_context: &FilterContext) {
         self.params.iter_mut().for_each(|param| param.assign_value(graphics_context));
     }
 
+    /// Returns `true`, if the settings of any param were changed.
+    pub fn rename_source(&mut self, settings: &mut SettingsContext, previous_name: &str, new_name: &str) -> bool {
+        self.params.iter_mut()
+            .fold(false, |changed, param| param.rename_source(settings, previous_name, new_name) || changed)
+    }
+
     pub fn enable_and_drop(self, graphics_context: &GraphicsContext) {
         #[allow(unused_assignments)]
         self.params.into_iter().for_each(|mut param| {
@@ -194,10 +497,54 @@ impl EffectParamsCustom {
                 Ok(param) => return param.enable_and_drop(graphics_context),
                 Err(param) => param,
             };
+            param = match param.downcast::<EffectParamCustomVec2>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomVec3>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomIVec2>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomIVec3>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomIVec4>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomImage>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomSource>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
             param = match param.downcast::<EffectParamCustomFFT>() {
                 Ok(param) => return param.enable_and_drop(graphics_context),
                 Err(param) => param,
             };
+            param = match param.downcast::<EffectParamCustomSpectrogram>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomBeat>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomAudioLevel>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
+            param = match param.downcast::<EffectParamCustomWaveform>() {
+                Ok(param) => return param.enable_and_drop(graphics_context),
+                Err(param) => param,
+            };
             panic!("No registered downcast to `enable_and_drop` a `Box<dyn BindableProperty>`. This is an implementation error.");
         });
     }
@@ -205,6 +552,10 @@ impl EffectParamsCustom {
     pub fn add_properties(&self, properties: &mut Properties) {
         self.params.iter().for_each(|param| param.add_properties(properties));
     }
+
+    pub fn messages(&self) -> impl Iterator<Item=Cow<str>> {
+        self.params.iter().filter_map(|param| param.message())
+    }
 }
 
 pub struct EffectParams {
@@ -217,6 +568,8 @@ pub struct EffectParams {
     pub elapsed_time_since_enabled: EffectParamFloat,
     pub elapsed_time_since_enabled_previous: EffectParamFloat,
     pub uv_size: EffectParamIVec2,
+    pub date: EffectParamVec4,
+    pub passes: EffectPasses,
     pub custom: EffectParamsCustom,
 }
 
@@ -235,6 +588,8 @@ impl EffectParams {
         self.elapsed_time_since_enabled.stage_value(graphics_context);
         self.elapsed_time_since_enabled_previous.stage_value(graphics_context);
         self.uv_size.stage_value(graphics_context);
+        self.date.stage_value(graphics_context);
+        self.passes.stage_values(graphics_context);
         self.custom.stage_values(graphics_context);
     }
 
@@ -248,6 +603,8 @@ impl EffectParams {
         self.elapsed_time_since_enabled.assign_value(graphics_context);
         self.elapsed_time_since_enabled_previous.assign_value(graphics_context);
         self.uv_size.assign_value(graphics_context);
+        self.date.assign_value(graphics_context);
+        self.passes.assign_values(graphics_context);
         self.custom.assign_values(graphics_context);
     }
 
@@ -261,6 +618,8 @@ impl EffectParams {
         self.elapsed_time_since_enabled.enable_and_drop(graphics_context);
         self.elapsed_time_since_enabled_previous.enable_and_drop(graphics_context);
         self.uv_size.enable_and_drop(graphics_context);
+        self.date.enable_and_drop(graphics_context);
+        self.passes.enable_and_drop(graphics_context);
         self.custom.enable_and_drop(graphics_context);
     }
 
@@ -272,6 +631,8 @@ impl EffectParams {
 pub struct PreparedEffect {
     pub effect: GraphicsContextDependentDisabled<GraphicsEffect>,
     pub shader_source: String,
+    /// Formatted preprocessor warnings, to be displayed in the UI
+    pub warnings: Vec<String>,
     pub params: EffectParams,
 }
 
@@ -285,23 +646,55 @@ impl PreparedEffect {
         self.params.add_properties(properties);
     }
 
-    pub fn create_effect<'a>(shader_path: &PathBuf, shader_source: &str, graphics_context: &'a GraphicsContext) -> Result<(GraphicsContextDependentEnabled<'a, GraphicsEffect>, PreprocessResult), Cow<'static, str>> {
+    pub fn create_effect<'a>(shader_path: &PathBuf, shader: &ExpandedSource, graphics_context: &'a GraphicsContext) -> Result<(GraphicsContextDependentEnabled<'a, GraphicsEffect>, PreprocessResult), Cow<'static, str>> {
         const EFFECT_SOURCE_TEMPLATE: &'static str = include_str!("../../examples/effect_template.hlsl");
+        const EFFECT_SOURCE_TEMPLATE_SHADERTOY: &'static str = include_str!("../../examples/effect_template_shadertoy.hlsl");
+
+        // Pragmas are stripped without removing lines, so that the source map stays valid.
+        let (preprocess_result, shader_source) = preprocess(&shader.source);
+
+        if preprocess_result.has_errors() {
+            throw!(format!(
+                "Could not preprocess the shader due to the following errors:\n{}",
+                preprocess_result.diagnostics().iter()
+                    .map(|diagnostic| diagnostic.format(&shader.source_map))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ));
+        }
 
-        let (preprocess_result, effect_source) = {
-            let pattern = Regex::new(r"(?P<shader>__SHADER__)").unwrap();
-            let effect_source = pattern.replace_all(EFFECT_SOURCE_TEMPLATE, shader_source);
+        let mode = preprocess_result.mode()
+            .unwrap_or_else(|| ShaderMode::detect(&shader_source));
 
-            let (preprocess_result, effect_source) = preprocess(&effect_source);
+        if mode == ShaderMode::Shadertoy && !preprocess_result.passes().is_empty() {
+            throw!("Passes declared using `#pragma shaderfilter pass` are not supported in the Shadertoy compatibility mode.");
+        }
 
-            (preprocess_result, effect_source.into_owned())
+        let (effect_source, source_map) = match mode {
+            ShaderMode::Default => splice_template(
+                Path::new("effect_template.hlsl"),
+                &prepare_pass_template(EFFECT_SOURCE_TEMPLATE, preprocess_result.passes()),
+                &shader_source,
+                &shader.source_map,
+            ),
+            ShaderMode::Shadertoy => {
+                // The translation keeps the number of lines, so that the source map stays valid.
+                let shader_source = shadertoy::translate(&shader_source);
+
+                splice_template(
+                    Path::new("effect_template_shadertoy.hlsl"),
+                    &shadertoy::prepare_template(EFFECT_SOURCE_TEMPLATE_SHADERTOY, &shader_source),
+                    &shader_source,
+                    &shader.source_map,
+                )
+            },
         };
 
-        let shader_path_c = CString::new(
-            shader_path.to_str().ok_or_else(|| {
-                "Specified shader path is not a valid UTF-8 string."
-            })?
-        ).map_err(|_| "Shader path cannot be converted to a C string.")?;
+        let shader_path_str = shader_path.to_str().ok_or_else(|| {
+            "Specified shader path is not a valid UTF-8 string."
+        })?;
+        let shader_path_c = CString::new(shader_path_str)
+            .map_err(|_| "Shader path cannot be converted to a C string.")?;
         let effect_source_c = CString::new(effect_source.clone())
             .map_err(|_| "Shader contents cannot be converted to a C string.")?;
 
@@ -315,9 +708,9 @@ impl PreparedEffect {
 
             result.map_err(|err| {
                 if let Some(err) = err {
-                    Cow::Owned(format!("Could not create the effect due to the following error: {}", err))
+                    Cow::Owned(format!("Could not create the effect due to the following error: {}", source_map.rewrite_message(&err.to_string(), shader_path_str)))
                 } else {
-                    Cow::Owned(format!("Could not create the effect due to the following error:\n{}", capture.to_string()))
+                    Cow::Owned(format!("Could not create the effect due to the following error:\n{}", source_map.rewrite_message(&capture.to_string(), shader_path_str)))
                 }
             })
         }?;
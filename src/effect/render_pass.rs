@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use obs_wrapper::{obs_sys::*, context::*, graphics::*};
+use crate::*;
+
+/// The texture an intermediate pass is rendered into.
+/// Must only be created and dropped within the graphics context.
+struct PassTextureRender {
+    texrender: *mut gs_texrender_t,
+    rendered: bool,
+}
+
+impl PassTextureRender {
+    fn new(format: PassFormat) -> Self {
+        let format = match format {
+            PassFormat::Rgba => gs_color_format_GS_RGBA,
+            PassFormat::Rgba16F => gs_color_format_GS_RGBA16F,
+            PassFormat::Rgba32F => gs_color_format_GS_RGBA32F,
+            PassFormat::R16F => gs_color_format_GS_R16F,
+            PassFormat::R32F => gs_color_format_GS_R32F,
+        };
+
+        Self {
+            texrender: unsafe {
+                gs_texrender_create(format, gs_zstencil_format_GS_ZS_NONE)
+            },
+            rendered: false,
+        }
+    }
+
+    /// Makes the texture the render target, so that the filter drawn at `source_dimensions` fills the texture.
+    /// Returns `false`, if the texture cannot be rendered into, in which case `end` must not be called.
+    fn begin(&mut self, dimensions: [u32; 2], source_dimensions: [u32; 2]) -> bool {
+        self.rendered = false;
+
+        if self.texrender.is_null() {
+            return false;
+        }
+
+        unsafe {
+            gs_texrender_reset(self.texrender);
+
+            if !gs_texrender_begin(self.texrender, dimensions[0], dimensions[1]) {
+                return false;
+            }
+
+            let clear_color: vec4 = std::mem::zeroed();
+
+            gs_clear(GS_CLEAR_COLOR, &clear_color, 0.0, 0);
+            gs_ortho(0.0, source_dimensions[0] as f32, 0.0, source_dimensions[1] as f32, -100.0, 100.0);
+            gs_blend_state_push();
+            gs_blend_function(gs_blend_type_GS_BLEND_ONE, gs_blend_type_GS_BLEND_ZERO);
+        }
+
+        true
+    }
+
+    fn end(&mut self) {
+        unsafe {
+            gs_blend_state_pop();
+            gs_texrender_end(self.texrender);
+        }
+
+        self.rendered = true;
+    }
+
+    /// The texture of the last rendered frame, or a null pointer, if nothing was rendered.
+    fn texture(&self) -> *mut gs_texture_t {
+        if !self.rendered {
+            return std::ptr::null_mut();
+        }
+
+        unsafe {
+            gs_texrender_get_texture(self.texrender)
+        }
+    }
+}
+
+impl Drop for PassTextureRender {
+    fn drop(&mut self) {
+        if !self.texrender.is_null() {
+            unsafe {
+                gs_texrender_destroy(self.texrender);
+            }
+        }
+    }
+}
+
+/// An intermediate pass, whose texture is bound to the `texture2d` uniform of the same name in later passes.
+/// The dimensions of the texture are bound to `builtin_uv_size_<NAME>`, if it is defined.
+pub struct EffectPass {
+    pub pass: RenderPass,
+    pub texture_param: EffectParamTexture,
+    pub size_param: Option<EffectParamIVec2>,
+    texture_render: Option<PassTextureRender>,
+}
+
+// Only ever accessed within the graphics context
+unsafe impl Send for EffectPass {}
+
+/// The passes declared using `#pragma shaderfilter pass`, rendered in order before the final `render` pass.
+/// The filter input is captured once per frame, then each pass is rendered by drawing the captured
+/// input with `builtin_pass` set to the index of the pass.
+#[derive(Default)]
+pub struct EffectPasses {
+    /// Bound to `builtin_pass`, which is only declared if there are any passes
+    pub pass_index: Option<EffectParamInt>,
+    pub passes: Vec<EffectPass>,
+    /// The filter input, bound to `image` in every pass
+    input_render: Option<PassTextureRender>,
+}
+
+impl EffectPasses {
+    /// Takes the uniforms of the passes out of `params`, so that they are not bound as custom params.
+    pub fn from<'a>(
+        pass_index: Option<EffectParamInt>,
+        passes: &[RenderPass],
+        params: &mut HashMap<String, Indexed<GraphicsContextDependentEnabled<'a, GraphicsEffectParam>>>,
+    ) -> Result<Self, Cow<'static, str>> {
+        let mut effect_passes = Vec::with_capacity(passes.len());
+
+        for pass in passes {
+            let texture_param = params.remove(&pass.name)
+                .ok_or_else(|| format!("Could not access the texture of pass `{}`.", pass.name))?
+                .into_inner();
+
+            if texture_param.param_type() != ShaderParamTypeKind::Texture {
+                throw!(format!("The texture of pass `{}` must be of type `{}`", pass.name, "texture2d"));
+            }
+
+            let size_param = match params.remove(&format!("builtin_uv_size_{}", pass.name)) {
+                Some(size_param) => {
+                    let size_param = size_param.into_inner();
+
+                    if size_param.param_type() != ShaderParamTypeKind::IVec2 {
+                        throw!(format!("Builtin field `builtin_uv_size_{}` must be of type `{}`", pass.name, "int2"));
+                    }
+
+                    Some(EffectParam::new(size_param.downcast().unwrap().disable()))
+                },
+                None => None,
+            };
+
+            effect_passes.push(EffectPass {
+                pass: pass.clone(),
+                texture_param: EffectParam::new(texture_param.downcast().unwrap().disable()),
+                size_param,
+                texture_render: None,
+            });
+        }
+
+        Ok(Self {
+            pass_index,
+            passes: effect_passes,
+            input_render: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    pub fn prepare_values(&mut self, source_dimensions: [u32; 2]) {
+        for pass in &mut self.passes {
+            if let Some(size_param) = pass.size_param.as_mut() {
+                let [width, height] = pass.pass.dimensions(source_dimensions);
+
+                size_param.prepare_value([width as i32, height as i32]);
+            }
+        }
+    }
+
+    pub fn stage_values(&mut self, graphics_context: &GraphicsContext) {
+        for pass in &mut self.passes {
+            if let Some(size_param) = pass.size_param.as_mut() {
+                size_param.stage_value(graphics_context);
+            }
+        }
+    }
+
+    /// Makes the texture of the filter input the render target, the filter input is then drawn into it.
+    /// Returns `false`, if the input cannot be captured, in which case `end_capture` must not be called.
+    pub fn begin_capture(&mut self, source_dimensions: [u32; 2]) -> bool {
+        self.input_render.get_or_insert_with(|| PassTextureRender::new(PassFormat::Rgba))
+            .begin(source_dimensions, source_dimensions)
+    }
+
+    pub fn end_capture(&mut self) {
+        if let Some(input_render) = self.input_render.as_mut() {
+            input_render.end();
+        }
+    }
+
+    /// Assigns the values shared by all passes, must be called before `render`.
+    pub fn assign_values(&mut self, graphics_context: &FilterContext) {
+        for pass in &mut self.passes {
+            if let Some(size_param) = pass.size_param.as_mut() {
+                size_param.assign_value(graphics_context);
+            }
+        }
+    }
+
+    /// Renders each pass into its texture, then draws the final `render` pass into the current render target.
+    /// All passes sample the filter input captured using `begin_capture` and `end_capture`.
+    pub fn render(&mut self, effect: *mut gs_effect_t, source_dimensions: [u32; 2], graphics_context: &GraphicsContext) {
+        let input = match self.input_render.as_ref().map(PassTextureRender::texture) {
+            Some(input) if !input.is_null() => input,
+            _ => return,
+        };
+
+        for index in 0..self.passes.len() {
+            let pass = &mut self.passes[index];
+            let format = pass.pass.format;
+            let dimensions = pass.pass.dimensions(source_dimensions);
+            let texture_render = pass.texture_render.get_or_insert_with(|| PassTextureRender::new(format));
+
+            if !texture_render.begin(dimensions, source_dimensions) {
+                continue;
+            }
+
+            self.bind_pass(index, graphics_context);
+
+            unsafe {
+                draw(effect, input, source_dimensions);
+            }
+
+            if let Some(texture_render) = self.passes[index].texture_render.as_mut() {
+                texture_render.end();
+            }
+        }
+
+        self.bind_pass(self.passes.len(), graphics_context);
+
+        unsafe {
+            draw(effect, input, source_dimensions);
+        }
+    }
+
+    /// Binds `builtin_pass` and the textures of the passes rendered before the pass `current`,
+    /// which is equal to the number of passes for the final pass.
+    fn bind_pass(&mut self, current: usize, graphics_context: &GraphicsContext) {
+        if let Some(pass_index) = self.pass_index.as_mut() {
+            let param = pass_index.param.as_enabled(graphics_context);
+
+            unsafe {
+                gs_effect_set_int(param.as_ptr(), current as i32);
+            }
+        }
+
+        for (index, pass) in self.passes.iter_mut().enumerate() {
+            // Only the textures of passes rendered before the current one are bound,
+            // so that the texture being rendered into is never sampled.
+            let texture = pass.texture_render.as_ref()
+                .filter(|_| index < current)
+                .map(|texture_render| texture_render.texture())
+                .unwrap_or(std::ptr::null_mut());
+            let param = pass.texture_param.param.as_enabled(graphics_context);
+
+            unsafe {
+                gs_effect_set_texture(param.as_ptr(), texture);
+            }
+        }
+    }
+
+    pub fn enable_and_drop(self, graphics_context: &GraphicsContext) {
+        if let Some(pass_index) = self.pass_index {
+            pass_index.enable_and_drop(graphics_context);
+        }
+
+        for pass in self.passes {
+            pass.texture_param.enable_and_drop(graphics_context);
+
+            if let Some(size_param) = pass.size_param {
+                size_param.enable_and_drop(graphics_context);
+            }
+
+            // The texture must be destroyed within the graphics context
+            std::mem::drop(pass.texture_render);
+        }
+    }
+}
+
+/// Draws the texture using the `Draw` technique of the effect, the same way `obs_source_process_filter_end` does.
+unsafe fn draw(effect: *mut gs_effect_t, image: *mut gs_texture_t, [width, height]: [u32; 2]) {
+    let technique = gs_effect_get_technique(effect, b"Draw\0".as_ptr() as *const c_char);
+    let image_param = gs_effect_get_param_by_name(effect, b"image\0".as_ptr() as *const c_char);
+
+    gs_effect_set_texture(image_param, image);
+
+    let passes = gs_technique_begin(technique);
+
+    for pass in 0..passes {
+        if gs_technique_begin_pass(technique, pass) {
+            gs_draw_sprite(image, 0, width, height);
+            gs_technique_end_pass(technique);
+        }
+    }
+
+    gs_technique_end(technique);
+}
@@ -0,0 +1,114 @@
+use obs_wrapper::obs_sys::*;
+
+/// A single channel floating point texture, whose rows are used as a ring buffer.
+/// New rows are copied into the texture one at a time, so the history is never uploaded as a whole.
+/// Must only be created, updated and dropped within the graphics context.
+pub struct RingTexture {
+    texture: *mut gs_texture_t,
+    /// A dynamic texture holding the row to be copied into `texture`
+    row_texture: *mut gs_texture_t,
+    width: usize,
+    height: usize,
+    /// The index of the most recently written row
+    head: usize,
+}
+
+impl RingTexture {
+    /// Creates a texture of the given dimensions, filled with zeros.
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        let zeros = vec![0f32; width * height];
+
+        unsafe {
+            let mut levels = [zeros.as_ptr() as *const u8];
+            let texture = gs_texture_create(
+                width as u32,
+                height as u32,
+                gs_color_format_GS_R32F,
+                1,
+                levels.as_mut_ptr(),
+                0,
+            );
+            let row_texture = gs_texture_create(
+                width as u32,
+                1,
+                gs_color_format_GS_R32F,
+                1,
+                std::ptr::null_mut(),
+                GS_DYNAMIC,
+            );
+
+            Self {
+                texture,
+                row_texture,
+                width,
+                height,
+                // The first row written is placed at index 0
+                head: height - 1,
+            }
+        }
+    }
+
+    pub fn dimensions(&self) -> [usize; 2] {
+        [self.width, self.height]
+    }
+
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    pub fn texture(&self) -> *mut gs_texture_t {
+        self.texture
+    }
+
+    /// Writes the row after the current head and advances the head to it.
+    /// Values beyond the width of the texture are ignored, missing values are set to zero.
+    pub fn push_row(&mut self, row: &[f32]) {
+        if self.texture.is_null() || self.row_texture.is_null() {
+            return;
+        }
+
+        let mut row_data = vec![0f32; self.width];
+        let copied_len = row.len().min(self.width);
+
+        row_data[..copied_len].copy_from_slice(&row[..copied_len]);
+        self.head = (self.head + 1) % self.height;
+
+        unsafe {
+            gs_texture_set_image(
+                self.row_texture,
+                row_data.as_ptr() as *const u8,
+                (self.width * std::mem::size_of::<f32>()) as u32,
+                false,
+            );
+            gs_copy_texture_region(
+                self.texture,
+                0,
+                self.head as u32,
+                self.row_texture,
+                0,
+                0,
+                self.width as u32,
+                1,
+            );
+        }
+    }
+}
+
+// Only ever accessed within the graphics context
+unsafe impl Send for RingTexture {}
+
+impl Drop for RingTexture {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.texture.is_null() {
+                gs_texture_destroy(self.texture);
+            }
+
+            if !self.row_texture.is_null() {
+                gs_texture_destroy(self.row_texture);
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use obs_wrapper::{obs_sys::*, source::*};
+
+/// A weak reference to a source picked by the user.
+/// The reference follows the source when it is renamed. When the source is removed,
+/// the source is looked up by its name again, in case a source of the same name is added.
+pub struct SourceReference {
+    /// The name of the source, as stored in the settings
+    name: CString,
+    weak_source: *mut obs_weak_source_t,
+    last_lookup: Option<Instant>,
+}
+
+impl SourceReference {
+    const LOOKUP_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(name: CString) -> Self {
+        Self {
+            name,
+            weak_source: std::ptr::null_mut(),
+            last_lookup: None,
+        }
+    }
+
+    pub fn name(&self) -> &CStr {
+        &self.name
+    }
+
+    /// Returns `true`, if a source is selected, but it does not currently exist.
+    pub fn is_missing(&self) -> bool {
+        !self.name.as_bytes().is_empty() && self.weak_source.is_null()
+    }
+
+    /// Points the reference at a different source, if the name differs from the current one.
+    pub fn set_name(&mut self, name: CString) {
+        if name != self.name {
+            self.release();
+            self.name = name;
+            self.last_lookup = None;
+        }
+    }
+
+    fn release(&mut self) {
+        if !self.weak_source.is_null() {
+            unsafe {
+                obs_weak_source_release(self.weak_source);
+            }
+
+            self.weak_source = std::ptr::null_mut();
+        }
+    }
+
+    /// Drops the reference, if the source was removed, and periodically looks the source up by
+    /// its name, if it is missing.
+    pub fn update(&mut self) {
+        if !self.weak_source.is_null() {
+            let source = self.get();
+
+            if source.is_null() {
+                self.release();
+            } else {
+                unsafe {
+                    obs_source_release(source);
+                }
+
+                return;
+            }
+        }
+
+        if self.name.as_bytes().is_empty() {
+            return;
+        }
+
+        if let Some(last_lookup) = self.last_lookup.as_ref() {
+            if last_lookup.elapsed() < Self::LOOKUP_INTERVAL {
+                return;
+            }
+        }
+
+        self.last_lookup = Some(Instant::now());
+
+        unsafe {
+            let source = obs_get_source_by_name(self.name.as_ptr());
+
+            if !source.is_null() {
+                self.weak_source = obs_source_get_weak_source(source);
+                obs_source_release(source);
+            }
+        }
+    }
+
+    /// Returns a strong reference to the source, which must be released using `obs_source_release`,
+    /// or a null pointer, if the source does not exist.
+    pub fn get(&self) -> *mut obs_source_t {
+        if self.weak_source.is_null() {
+            return std::ptr::null_mut();
+        }
+
+        unsafe {
+            let source = obs_weak_source_get_source(self.weak_source);
+
+            if !source.is_null() && obs_source_removed(source) {
+                obs_source_release(source);
+                return std::ptr::null_mut();
+            }
+
+            source
+        }
+    }
+
+    /// The dimensions of the source, or `[0, 0]`, if it does not exist.
+    pub fn dimensions(&self) -> [u32; 2] {
+        let source = self.get();
+
+        if source.is_null() {
+            return [0, 0];
+        }
+
+        unsafe {
+            let dimensions = [obs_source_get_width(source), obs_source_get_height(source)];
+
+            obs_source_release(source);
+            dimensions
+        }
+    }
+}
+
+// Weak references may be used from any thread
+unsafe impl Send for SourceReference {}
+
+impl Drop for SourceReference {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Collects the renames of all sources, so that settings referring to a source by its name can follow it
+/// across reloads of the shader and restarts of OBS. Disconnected from the signal when dropped.
+pub struct SourceRenameSignal {
+    /// Passed to the signal callback, the heap allocation keeps its address stable
+    renames: Box<Mutex<Vec<(String, String)>>>,
+}
+
+const SOURCE_RENAME_SIGNAL: &[u8] = b"source_rename\0";
+
+unsafe extern "C" fn source_renamed(param: *mut c_void, calldata: *mut calldata_t) {
+    let renames = &*(param as *const Mutex<Vec<(String, String)>>);
+    let mut previous_name: *const c_char = std::ptr::null();
+    let mut new_name: *const c_char = std::ptr::null();
+
+    if calldata_get_string(calldata, b"prev_name\0".as_ptr() as *const c_char, &mut previous_name)
+        && calldata_get_string(calldata, b"new_name\0".as_ptr() as *const c_char, &mut new_name)
+        && !previous_name.is_null()
+        && !new_name.is_null()
+    {
+        renames.lock().unwrap().push((
+            CStr::from_ptr(previous_name).to_string_lossy().into_owned(),
+            CStr::from_ptr(new_name).to_string_lossy().into_owned(),
+        ));
+    }
+}
+
+impl SourceRenameSignal {
+    pub fn connect() -> Self {
+        let result = Self {
+            renames: Box::new(Mutex::new(Vec::new())),
+        };
+
+        unsafe {
+            signal_handler_connect(
+                obs_get_signal_handler(),
+                SOURCE_RENAME_SIGNAL.as_ptr() as *const c_char,
+                Some(source_renamed),
+                result.param(),
+            );
+        }
+
+        result
+    }
+
+    fn param(&self) -> *mut c_void {
+        &*self.renames as *const Mutex<Vec<(String, String)>> as *mut c_void
+    }
+
+    /// Returns the `(previous name, new name)` pairs of the sources renamed since the last call, in order.
+    pub fn take_renames(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.renames.lock().unwrap())
+    }
+}
+
+impl Drop for SourceRenameSignal {
+    fn drop(&mut self) {
+        unsafe {
+            signal_handler_disconnect(
+                obs_get_signal_handler(),
+                SOURCE_RENAME_SIGNAL.as_ptr() as *const c_char,
+                Some(source_renamed),
+                self.param(),
+            );
+        }
+    }
+}
+
+thread_local! {
+    /// Sources currently being rendered into textures, used to prevent infinite recursion,
+    /// when a source is picked as the input of a filter applied to itself.
+    static SOURCES_BEING_RENDERED: RefCell<Vec<*mut obs_source_t>> = RefCell::new(Vec::new());
+}
+
+/// Renders a source into a texture, which can then be bound to an effect param.
+/// Must only be created and dropped within the graphics context.
+pub struct SourceTextureRender {
+    texrender: *mut gs_texrender_t,
+    rendered: bool,
+}
+
+impl SourceTextureRender {
+    pub fn new() -> Self {
+        Self {
+            texrender: unsafe {
+                gs_texrender_create(gs_color_format_GS_RGBA, gs_zstencil_format_GS_ZS_NONE)
+            },
+            rendered: false,
+        }
+    }
+
+    /// Renders the source into the texture. If the source is null, or it is already being rendered
+    /// further up the stack, nothing is rendered and `texture` returns a null pointer.
+    pub fn render(&mut self, source: *mut obs_source_t) {
+        self.rendered = false;
+
+        if source.is_null() || self.texrender.is_null() {
+            return;
+        }
+
+        let recursive = SOURCES_BEING_RENDERED.with(|sources| {
+            sources.borrow().contains(&source)
+        });
+
+        if recursive {
+            return;
+        }
+
+        unsafe {
+            let width = obs_source_get_width(source);
+            let height = obs_source_get_height(source);
+
+            if width == 0 || height == 0 {
+                return;
+            }
+
+            gs_texrender_reset(self.texrender);
+
+            if !gs_texrender_begin(self.texrender, width, height) {
+                return;
+            }
+
+            let clear_color: vec4 = std::mem::zeroed();
+
+            gs_clear(GS_CLEAR_COLOR, &clear_color, 0.0, 0);
+            gs_ortho(0.0, width as f32, 0.0, height as f32, -100.0, 100.0);
+            gs_blend_state_push();
+            gs_blend_function(gs_blend_type_GS_BLEND_ONE, gs_blend_type_GS_BLEND_ZERO);
+
+            SOURCES_BEING_RENDERED.with(|sources| sources.borrow_mut().push(source));
+            obs_source_video_render(source);
+            SOURCES_BEING_RENDERED.with(|sources| sources.borrow_mut().pop());
+
+            gs_blend_state_pop();
+            gs_texrender_end(self.texrender);
+        }
+
+        self.rendered = true;
+    }
+
+    /// The texture of the last rendered frame, or a null pointer, if nothing was rendered.
+    pub fn texture(&self) -> *mut gs_texture_t {
+        if !self.rendered {
+            return std::ptr::null_mut();
+        }
+
+        unsafe {
+            gs_texrender_get_texture(self.texrender)
+        }
+    }
+}
+
+// Only ever accessed within the graphics context
+unsafe impl Send for SourceTextureRender {}
+
+impl Drop for SourceTextureRender {
+    fn drop(&mut self) {
+        if !self.texrender.is_null() {
+            unsafe {
+                gs_texrender_destroy(self.texrender);
+            }
+        }
+    }
+}
+
+/// A list property for picking a source or a scene.
+/// The value is the name of the source, so it can be read using a
+/// `PropertyDescriptorSpecializationString` descriptor of the same name.
+#[derive(Clone, Debug, Default)]
+pub struct PropertyDescriptorSpecializationSourceList {
+    /// Only sources with any of these output flags are listed, e.g. `OBS_SOURCE_VIDEO`
+    pub output_flags: u32,
+    /// The selected source, which is listed even if it does not exist anymore
+    pub selected: Option<CString>,
+}
+
+struct EnumSourcesParam {
+    output_flags: u32,
+    names: Vec<CString>,
+}
+
+unsafe extern "C" fn enum_sources(param: *mut c_void, source: *mut obs_source_t) -> bool {
+    let param = &mut *(param as *mut EnumSourcesParam);
+
+    if obs_source_get_output_flags(source) & param.output_flags != 0 {
+        let name = obs_source_get_name(source);
+
+        if !name.is_null() {
+            param.names.push(CStr::from_ptr(name).to_owned());
+        }
+    }
+
+    true
+}
+
+impl PropertyDescriptorSpecialization for PropertyDescriptorSpecializationSourceList {
+    unsafe fn create_property(
+        &self,
+        name: *const c_char,
+        description: *const c_char,
+        properties: *mut obs_properties_t,
+    ) -> *mut obs_property_t {
+        let property = obs_properties_add_list(
+            properties,
+            name,
+            description,
+            obs_combo_type_OBS_COMBO_TYPE_LIST,
+            obs_combo_format_OBS_COMBO_FORMAT_STRING,
+        );
+        let mut param = EnumSourcesParam {
+            output_flags: self.output_flags,
+            names: Vec::new(),
+        };
+
+        obs_enum_sources(Some(enum_sources), &mut param as *mut _ as *mut c_void);
+        obs_enum_scenes(Some(enum_sources), &mut param as *mut _ as *mut c_void);
+
+        let mut names = param.names;
+
+        names.sort();
+
+        obs_property_list_add_string(
+            property,
+            b"(None)\0".as_ptr() as *const c_char,
+            b"\0".as_ptr() as *const c_char,
+        );
+
+        if let Some(selected_name) = self.selected.as_ref() {
+            if !selected_name.as_bytes().is_empty() && !names.contains(selected_name) {
+                let missing_name = CString::new(format!("{} (missing)", selected_name.to_string_lossy())).unwrap();
+                obs_property_list_add_string(property, missing_name.as_ptr(), selected_name.as_ptr());
+            }
+        }
+
+        for name in &names {
+            obs_property_list_add_string(property, name.as_ptr(), name.as_ptr());
+        }
+
+        property
+    }
+}
@@ -4,14 +4,13 @@
 #![feature(associated_type_bounds)]
 
 use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{RwLock, Arc, Weak};
+use std::sync::{Mutex, RwLock, Arc, Weak};
 use std::borrow::Cow;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::path::PathBuf;
-use std::fs::File;
 use std::ffi::{CStr, CString};
-use std::io::Read;
 use ordered_float::OrderedFloat;
 use lazy_static::lazy_static;
 use obs_wrapper::{
@@ -21,14 +20,24 @@ use obs_wrapper::{
     obs_register_module,
     prelude::*,
     source::*,
-    audio::*,
 };
-use fourier::*;
-use num_complex::Complex;
 use util::*;
 use effect::*;
 use preprocessor::*;
+use beat::{BeatDetector, BeatParameters, BeatState};
+use loudness::{LevelMeter, LevelMeterParameters, Levels};
+use audio_input::{AudioInput, AudioInputDescriptor};
+use spectrum::{
+    FFTResult, RawSpectrum, SpectrumAnalyzer, SpectrumAnalyzerParameters,
+    FrequencyMapping, LogFrequencyParameters, SpectrumProcessor, SpectrumProcessorParameters,
+    SpectrumScale, WindowFunction,
+};
 mod mel;
+mod beat;
+mod loudness;
+mod spectrum;
+#[cfg(test)]
+mod test_util;
 
 macro_rules! throw {
     ($e:expr) => {{
@@ -38,6 +47,7 @@ macro_rules! throw {
 }
 
 mod util;
+mod audio_input;
 mod effect;
 mod preprocessor;
 
@@ -54,75 +64,72 @@ pub trait GlobalStateComponentType {
     // fn register_callback(self: &Arc<Self>, callback: Box<dyn Fn(&Self::Result) + Send + Sync>);
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum WindowFunction {
-    None,
-    Blackman,
-    Cosine {
-        a: OrderedFloat<f64>,
-        b: OrderedFloat<f64>,
-        c: OrderedFloat<f64>,
-        d: OrderedFloat<f64>,
-    },
-    Hamming,
-    Hanning,
-    Nuttall,
-    Triangular,
-}
-
-impl WindowFunction {
-    pub fn generate_coefficients(self, len: usize) -> Vec<f32> {
-        use apodize::*;
-        use WindowFunction::*;
-
-        match self {
-            None => std::iter::repeat(1.0).take(len).collect::<Vec<_>>(),
-            Blackman => blackman_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
-            Cosine { a, b, c, d } => cosine_iter(*a, *b, *c, *d, len).map(|coef| coef as f32).collect::<Vec<_>>(),
-            Hamming => hamming_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
-            Hanning => hanning_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
-            Nuttall => nuttall_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
-            Triangular => triangular_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct GlobalStateAudioFFTDescriptor {
-    mix: usize,
+    input: AudioInputDescriptor,
     channel: usize,
     dampening_factor_attack: OrderedFloat<f64>,
     dampening_factor_release: OrderedFloat<f64>,
     window_function: WindowFunction,
+    /// The number of samples analysed at once, `0` to analyse the samples of a single video frame
+    fft_size: usize,
+    /// The number of new samples between two analyses, `0` to use the number of samples of a single video frame
+    hop_size: usize,
+    /// The analysed samples are padded with zeros to `zero_padding` times their length
+    zero_padding: usize,
+    scale: SpectrumScale,
     mel_enabled: bool,
     n_mels: usize,
     f_min: OrderedFloat<f32>,
     f_max: OrderedFloat<f32>,
+    mel_scale: SpectrumScale,
+    mel_formula: mel::MelFormula,
+    mel_area_normalization: bool,
+    log_enabled: bool,
+    log_bins_per_octave: usize,
+    log_f_min: OrderedFloat<f32>,
+    log_f_max: OrderedFloat<f32>,
+    /// The number of Mel-frequency cepstral coefficients, `0` if they are not computed
+    mfcc_coefficients: usize,
+    chroma_enabled: bool,
 }
 
 impl GlobalStateAudioFFTDescriptor {
     pub fn new(
-        mix: usize,
+        input: AudioInputDescriptor,
         channel: usize,
         dampening_factor_attack: f64,
         dampening_factor_release: f64,
         window_function: WindowFunction,
     ) -> Self {
         Self {
-            mix,
+            input,
             channel,
             dampening_factor_attack: OrderedFloat(dampening_factor_attack),
             dampening_factor_release: OrderedFloat(dampening_factor_release),
             window_function,
+            fft_size: 0,
+            hop_size: 0,
+            zero_padding: 1,
+            scale: SpectrumScale::SqrtMagnitude,
             mel_enabled: false,
             n_mels: 128,
             f_min: OrderedFloat(20.0),
             f_max: OrderedFloat(8000.0),
+            mel_scale: SpectrumScale::Normalized,
+            mel_formula: mel::MelFormula::Htk,
+            mel_area_normalization: false,
+            log_enabled: false,
+            log_bins_per_octave: 3,
+            log_f_min: OrderedFloat(20.0),
+            log_f_max: OrderedFloat(20000.0),
+            mfcc_coefficients: 0,
+            chroma_enabled: false,
         }
     }
     
     pub fn new_with_mel(
-        mix: usize,
+        input: AudioInputDescriptor,
         channel: usize,
         dampening_factor_attack: f64,
         dampening_factor_release: f64,
@@ -133,239 +140,591 @@ impl GlobalStateAudioFFTDescriptor {
         f_max: f32,
     ) -> Self {
         Self {
-            mix,
-            channel,
-            dampening_factor_attack: OrderedFloat(dampening_factor_attack),
-            dampening_factor_release: OrderedFloat(dampening_factor_release),
-            window_function,
             mel_enabled,
             n_mels,
             f_min: OrderedFloat(f_min),
             f_max: OrderedFloat(f_max),
+            ..Self::new(input, channel, dampening_factor_attack, dampening_factor_release, window_function)
+        }
+    }
+
+    pub fn with_sizes(self, fft_size: usize, hop_size: usize, zero_padding: usize) -> Self {
+        Self {
+            fft_size,
+            hop_size,
+            zero_padding: zero_padding.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_scales(self, scale: SpectrumScale, mel_scale: SpectrumScale) -> Self {
+        Self {
+            scale,
+            mel_scale,
+            ..self
+        }
+    }
+
+    pub fn with_mel_filters(self, mel_formula: mel::MelFormula, mel_area_normalization: bool) -> Self {
+        Self {
+            mel_formula,
+            mel_area_normalization,
+            ..self
+        }
+    }
+
+    pub fn with_log_frequencies(self, log_enabled: bool, log_bins_per_octave: usize, log_f_min: f32, log_f_max: f32) -> Self {
+        Self {
+            log_enabled,
+            log_bins_per_octave: log_bins_per_octave.max(1),
+            log_f_min: OrderedFloat(log_f_min),
+            log_f_max: OrderedFloat(log_f_max),
+            ..self
+        }
+    }
+
+    pub fn with_features(self, mfcc_coefficients: usize, chroma_enabled: bool) -> Self {
+        Self {
+            mfcc_coefficients,
+            chroma_enabled,
+            ..self
+        }
+    }
+
+    /// The raw spectrum is shared by all FFT components, which differ only in their post-processing.
+    fn spectrum_descriptor(&self) -> GlobalStateAudioSpectrumDescriptor {
+        GlobalStateAudioSpectrumDescriptor {
+            input: self.input.clone(),
+            channel: self.channel,
+            window_function: self.window_function,
+            fft_size: self.fft_size,
+            hop_size: self.hop_size,
+            zero_padding: self.zero_padding,
+        }
+    }
+
+    fn processor_parameters(&self) -> SpectrumProcessorParameters {
+        SpectrumProcessorParameters {
+            dampening_factor_attack: *self.dampening_factor_attack,
+            dampening_factor_release: *self.dampening_factor_release,
+            scale: self.scale,
+            log: if self.log_enabled {
+                Some(LogFrequencyParameters {
+                    bins_per_octave: self.log_bins_per_octave,
+                    f_min: *self.log_f_min,
+                    f_max: *self.log_f_max,
+                })
+            } else {
+                None
+            },
+            // The MFCCs are computed using the Mel filter bank
+            mel: if self.mel_enabled || self.mfcc_coefficients > 0 {
+                Some(mel::MelParameters {
+                    n_mels: self.n_mels,
+                    f_min: *self.f_min,
+                    f_max: *self.f_max,
+                    formula: self.mel_formula,
+                    area_normalization: self.mel_area_normalization,
+                })
+            } else {
+                None
+            },
+            mel_scale: self.mel_scale,
+            mfcc_coefficients: Some(self.mfcc_coefficients).filter(|&mfcc_coefficients| mfcc_coefficients > 0),
+            chroma: self.chroma_enabled,
         }
     }
 }
 
-#[derive(Clone)]
-pub struct FFTResult {
-    batch_number: usize,
-    frequency_spectrum: Arc<Vec<f32>>,
-    mel_spectrum: Option<Arc<Vec<f32>>>,
+/// Post-processes the shared raw spectrum, without performing an FFT of its own.
+pub struct GlobalStateAudioFFT {
+    spectrum: Arc<GlobalStateAudioSpectrum>,
+    processor: Mutex<SpectrumProcessor>,
 }
 
-pub struct GlobalStateAudioFFTMutable {
-    audio_output: Option<AudioOutput>,
-    sample_buffer: VecDeque<f32>,
-    window: Arc<Vec<f32>>,
+impl GlobalStateAudioFFT {
+    fn audio_input_error(&self) -> Option<String> {
+        self.spectrum.audio_input_error()
+    }
+}
+
+impl GlobalStateComponentType for GlobalStateAudioFFT {
+    type Descriptor = GlobalStateAudioFFTDescriptor;
+    type Result = FFTResult;
+
+    fn create(descriptor: &Self::Descriptor) -> Arc<Self> {
+        Arc::new(Self {
+            spectrum: GLOBAL_STATE.request_audio_spectrum(&descriptor.spectrum_descriptor()),
+            processor: Mutex::new(SpectrumProcessor::new(descriptor.processor_parameters())),
+        })
+    }
+
+    fn retrieve_result(self: &Arc<Self>) -> Option<Self::Result> {
+        let raw_spectrum = self.spectrum.retrieve_result()?;
+        let mut processor = self.processor.lock().unwrap();
+
+        Some(processor.process(&raw_spectrum).clone())
+    }
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GlobalStateAudioBeatDescriptor {
+    fft: GlobalStateAudioFFTDescriptor,
+    bands: usize,
+    f_min: OrderedFloat<f32>,
+    f_max: OrderedFloat<f32>,
+    sensitivity: OrderedFloat<f32>,
+    bpm_min: OrderedFloat<f64>,
+    bpm_max: OrderedFloat<f64>,
+}
+
+impl GlobalStateAudioBeatDescriptor {
+    /// The detection is tuned to the default scale, so the scales of the FFT descriptor are ignored.
+    pub fn new(fft: GlobalStateAudioFFTDescriptor, beat_parameters: &BeatParameters) -> Self {
+        Self {
+            fft: fft.with_scales(SpectrumScale::SqrtMagnitude, SpectrumScale::Normalized),
+            bands: beat_parameters.bands,
+            f_min: OrderedFloat(beat_parameters.f_min),
+            f_max: OrderedFloat(beat_parameters.f_max),
+            sensitivity: OrderedFloat(beat_parameters.sensitivity),
+            bpm_min: OrderedFloat(beat_parameters.bpm_min),
+            bpm_max: OrderedFloat(beat_parameters.bpm_max),
+        }
+    }
+
+    fn beat_parameters(&self) -> BeatParameters {
+        BeatParameters {
+            bands: self.bands,
+            f_min: *self.f_min,
+            f_max: *self.f_max,
+            sensitivity: *self.sensitivity,
+            bpm_min: *self.bpm_min,
+            bpm_max: *self.bpm_max,
+        }
+    }
+}
+
+pub struct GlobalStateAudioBeatMutable {
+    detector: BeatDetector,
+    /// The batch number of the last spectrum processed by the detector
+    last_batch_number: Option<usize>,
+    /// The time of the last spectrum processed by the detector
+    last_time: f64,
+}
+
+/// Detects beats in the spectra of the FFT component, so that all filters
+/// using the same analysis share a single detection.
+pub struct GlobalStateAudioBeat {
+    audio_fft: Arc<GlobalStateAudioFFT>,
+    mutable: Mutex<GlobalStateAudioBeatMutable>,
+}
+
+impl GlobalStateAudioBeat {
+    fn audio_input_error(&self) -> Option<String> {
+        self.audio_fft.audio_input_error()
+    }
+}
+
+impl GlobalStateComponentType for GlobalStateAudioBeat {
+    type Descriptor = GlobalStateAudioBeatDescriptor;
+    type Result = BeatState;
+
+    fn create(descriptor: &Self::Descriptor) -> Arc<Self> {
+        Arc::new(Self {
+            audio_fft: GLOBAL_STATE.request_audio_fft(&descriptor.fft),
+            mutable: Mutex::new(GlobalStateAudioBeatMutable {
+                detector: BeatDetector::new(descriptor.beat_parameters()),
+                last_batch_number: None,
+                last_time: 0.0,
+            }),
+        })
+    }
+
+    fn retrieve_result(self: &Arc<Self>) -> Option<Self::Result> {
+        let fft_result = self.audio_fft.retrieve_result()?;
+        let mut mutable = self.mutable.lock().unwrap();
+
+        if mutable.last_batch_number != Some(fft_result.batch_number) {
+            let spectrum = &fft_result.frequency_spectrum;
+            // The spectrum covers the frequencies up to half the sample rate
+            let bin_width = fft_result.sample_rate as f32 / (2 * spectrum.len().max(1)) as f32;
+
+            mutable.last_batch_number = Some(fft_result.batch_number);
+            mutable.last_time = fft_result.time;
+            mutable.detector.process(spectrum, bin_width, fft_result.time);
+        }
+
+        Some(mutable.detector.state(mutable.last_time))
+    }
+}
+
+/// Identifies the raw spectrum, which is computed once for all FFT components with the same input and sizes.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GlobalStateAudioSpectrumDescriptor {
+    input: AudioInputDescriptor,
+    channel: usize,
+    window_function: WindowFunction,
+    /// The number of samples analysed at once, `0` to analyse the samples of a single video frame
+    fft_size: usize,
+    /// The number of new samples between two analyses, `0` to use the number of samples of a single video frame
+    hop_size: usize,
+    /// The analysed samples are padded with zeros to `zero_padding` times their length
+    zero_padding: usize,
+}
+
+pub struct GlobalStateAudioSpectrumMutable {
+    /// Created when the first samples are received, so that the sample rate is known,
+    /// and recreated along with its FFT plan only when the sample rate or the sizes change
+    analyzer: Option<SpectrumAnalyzer>,
     /// Set during `retrieve_result` to indicate that the analysis of the next
     /// batch should be performed.
-    next_batch_scheduled: AtomicBool,
-    /// The result of the analysis.
-    result: Option<FFTResult>,
+    next_batch_scheduled: bool,
 }
 
-impl Default for GlobalStateAudioFFTMutable {
+impl Default for GlobalStateAudioSpectrumMutable {
     fn default() -> Self {
         Self {
-            audio_output: Default::default(),
-            sample_buffer: Default::default(),
-            window: Arc::new(Vec::new()),
-            next_batch_scheduled: AtomicBool::new(true),
-            result: None,
+            analyzer: None,
+            next_batch_scheduled: true,
         }
     }
 }
 
-pub struct GlobalStateAudioFFT {
-    descriptor: GlobalStateAudioFFTDescriptor,
-    mutable: Arc<RwLock<GlobalStateAudioFFTMutable>>,
+/// Performs the FFT of the audio samples.
+pub struct GlobalStateAudioSpectrum {
+    descriptor: GlobalStateAudioSpectrumDescriptor,
+    /// Kept outside of `mutable`, because OBS holds a lock while calling the capture callback,
+    /// which locks `mutable`, and the same lock is taken when the capture is updated.
+    audio_input: Mutex<Option<Box<dyn AudioInput>>>,
+    /// A mutex, because the FFT plan of the analyzer may not be shared between threads
+    mutable: Arc<Mutex<GlobalStateAudioSpectrumMutable>>,
 }
 
-impl GlobalStateAudioFFT {
-    fn get_samples_per_frame() -> usize {
-        let audio_info = ObsAudioInfo::get()
-            .expect("Audio info not accessible.");
+impl GlobalStateAudioSpectrum {
+    /// A problem with the audio input to be displayed to the user, if there is one.
+    fn audio_input_error(&self) -> Option<String> {
+        self.audio_input.lock().unwrap().as_ref()
+            .and_then(|audio_input| audio_input.error().map(Cow::into_owned))
+    }
+
+    fn get_samples_per_frame(sample_rate: f64) -> usize {
         let video_info = ObsVideoInfo::get()
             .expect("Video info not accessible.");
         let framerate = video_info.framerate();
 
-        (audio_info.samples_per_second() as usize * framerate.denominator as usize)
+        (sample_rate as usize * framerate.denominator as usize)
             / framerate.numerator as usize
     }
 
-    fn render_frames_to_time_elapsed(render_frames: usize) -> f64 {
-        let video_info = ObsVideoInfo::get()
-            .expect("Video info not accessible.");
-        let framerate = video_info.framerate();
+    /// Resolves the sizes, which depend on the number of samples of a single video frame.
+    fn analyzer_parameters(&self, sample_rate: f64) -> SpectrumAnalyzerParameters {
+        let samples_per_frame = Self::get_samples_per_frame(sample_rate);
+        let descriptor = &self.descriptor;
 
-        (render_frames as f64 * framerate.denominator as f64) / framerate.numerator as f64
+        SpectrumAnalyzerParameters {
+            window_function: descriptor.window_function,
+            fft_size: if descriptor.fft_size > 0 { descriptor.fft_size } else { samples_per_frame },
+            hop_size: if descriptor.hop_size > 0 { descriptor.hop_size } else { samples_per_frame },
+            zero_padding: descriptor.zero_padding,
+        }
     }
 
-    fn perform_analysis(
-        samples: impl Iterator<Item=f32> + ExactSizeIterator,
-        window: &[f32],
-    ) -> Vec<f32> {
-        assert_eq!(samples.len(), window.len());
+    fn process_audio_data(this: &Weak<Self>, current_samples: &[f32], sample_rate: f64) {
+        let this = if let Some(this) = Weak::upgrade(this) {
+            this
+        } else {
+            // The audio spectrum component no longer exists, bail.
+            return;
+        };
+
+        let parameters = this.analyzer_parameters(sample_rate);
+        let mut mutable = this.mutable.lock().unwrap();
+        let recreate_analyzer = mutable.analyzer.as_ref().map(|analyzer| {
+            analyzer.sample_rate() != sample_rate || analyzer.parameters() != &parameters
+        }).unwrap_or(true);
+
+        if recreate_analyzer {
+            mutable.analyzer = Some(SpectrumAnalyzer::new(parameters, sample_rate));
+        }
 
-        let len = samples.len();
-        let mut fft_data: Vec<Complex<f32>> = samples.zip(window.iter()).map(|(sample, window_coefficient)| {
-            Complex::new(sample * window_coefficient, 0.0)
-        }).collect::<Vec<_>>();
-        let fft = fourier::create_fft_f32(len);
+        mutable.analyzer.as_mut().unwrap().push_samples(current_samples);
 
-        fft.transform_in_place(&mut fft_data, Transform::Fft);
+        if !mutable.next_batch_scheduled {
+            return;
+        }
 
-        fft_data.into_iter().take(len / 2).map(|complex| {
-            // normalize according to https://www.sjsu.edu/people/burford.furman/docs/me120/FFT_tutorial_NI.pdf
-            (complex.norm() * 4.0 / len as f32).sqrt()
-        }).collect::<Vec<_>>()
+        if mutable.analyzer.as_mut().unwrap().analyze() {
+            mutable.next_batch_scheduled = false;
+        }
     }
+}
 
-    fn process_audio_data<'a>(this: &Weak<Self>, audio_data: AudioData<'a, ()>) {
+impl GlobalStateComponentType for GlobalStateAudioSpectrum {
+    type Descriptor = GlobalStateAudioSpectrumDescriptor;
+    type Result = RawSpectrum;
+
+    fn create(descriptor: &Self::Descriptor) -> Arc<Self> {
+        let result = Arc::new(Self {
+            descriptor: descriptor.clone(),
+            audio_input: Mutex::new(None),
+            mutable: Default::default(),
+        });
+
+        let audio_input = descriptor.input.connect(
+            descriptor.channel,
+            {
+                let self_cloned = Arc::downgrade(&result);
+
+                Box::new(move |samples, sample_rate| {
+                    Self::process_audio_data(&self_cloned, samples, sample_rate);
+                })
+            },
+        );
+
+        *result.audio_input.lock().unwrap() = Some(audio_input);
+
+        result
+    }
+
+    fn retrieve_result(self: &Arc<Self>) -> Option<Self::Result> {
+        if let Some(audio_input) = self.audio_input.lock().unwrap().as_ref() {
+            audio_input.update();
+        }
+
+        let mut mutable = self.mutable.lock().unwrap();
+
+        mutable.next_batch_scheduled = true;
+        mutable.analyzer.as_ref().and_then(|analyzer| analyzer.result().cloned())
+    }
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GlobalStateAudioWaveformDescriptor {
+    input: AudioInputDescriptor,
+    channel: usize,
+    /// The number of samples provided to the shader
+    length: usize,
+}
+
+impl GlobalStateAudioWaveformDescriptor {
+    pub fn new(input: AudioInputDescriptor, channel: usize, length: usize) -> Self {
+        Self {
+            input,
+            channel,
+            length,
+        }
+    }
+
+    /// Twice the length is kept, so that a zero crossing trigger can be found.
+    fn history_length(&self) -> usize {
+        self.length * 2
+    }
+}
+
+#[derive(Default)]
+pub struct GlobalStateAudioWaveformMutable {
+    /// The most recent samples
+    sample_buffer: VecDeque<f32>,
+}
+
+pub struct GlobalStateAudioWaveform {
+    descriptor: GlobalStateAudioWaveformDescriptor,
+    /// Kept outside of `mutable`, because OBS holds a lock while calling the capture callback,
+    /// which locks `mutable`, and the same lock is taken when the capture is updated.
+    audio_input: Mutex<Option<Box<dyn AudioInput>>>,
+    mutable: Arc<RwLock<GlobalStateAudioWaveformMutable>>,
+}
+
+impl GlobalStateAudioWaveform {
+    /// A problem with the audio input to be displayed to the user, if there is one.
+    fn audio_input_error(&self) -> Option<String> {
+        self.audio_input.lock().unwrap().as_ref()
+            .and_then(|audio_input| audio_input.error().map(Cow::into_owned))
+    }
+
+    fn process_audio_data(this: &Weak<Self>, current_samples: &[f32], _sample_rate: f64) {
         let this = if let Some(this) = Weak::upgrade(this) {
             this
         } else {
-            // The audio FFT component no longer exists, bail.
+            // The audio waveform component no longer exists, bail.
             return;
         };
 
         let mut mutable_write = this.mutable.write().unwrap();
+        let history_length = this.descriptor.history_length();
 
-        let current_samples = if let Some(samples) = audio_data.samples_normalized(this.descriptor.channel) {
-            samples
+        mutable_write.sample_buffer.extend(current_samples);
+
+        let samples_over_history_length = mutable_write.sample_buffer.len().saturating_sub(history_length);
+
+        if samples_over_history_length > 0 {
+            mutable_write.sample_buffer.drain(0..samples_over_history_length);
+        }
+    }
+}
+
+impl GlobalStateComponentType for GlobalStateAudioWaveform {
+    type Descriptor = GlobalStateAudioWaveformDescriptor;
+    /// The most recent samples, up to twice the requested length
+    type Result = Vec<f32>;
+
+    fn create(descriptor: &Self::Descriptor) -> Arc<Self> {
+        let result = Arc::new(Self {
+            descriptor: descriptor.clone(),
+            audio_input: Mutex::new(None),
+            mutable: Default::default(),
+        });
+
+        let audio_input = descriptor.input.connect(
+            descriptor.channel,
+            {
+                let self_cloned = Arc::downgrade(&result);
+
+                Box::new(move |samples, sample_rate| {
+                    Self::process_audio_data(&self_cloned, samples, sample_rate);
+                })
+            },
+        );
+
+        *result.audio_input.lock().unwrap() = Some(audio_input);
+
+        result
+    }
+
+    fn retrieve_result(self: &Arc<Self>) -> Option<Self::Result> {
+        if let Some(audio_input) = self.audio_input.lock().unwrap().as_ref() {
+            audio_input.update();
+        }
+
+        let mutable_read = self.mutable.read().unwrap();
+
+        if mutable_read.sample_buffer.is_empty() {
+            None
         } else {
-            // No samples captured, bail.
-            return;
-        };
+            Some(mutable_read.sample_buffer.iter().copied().collect())
+        }
+    }
+}
 
-        mutable_write.sample_buffer.extend(current_samples);
+/// Returns the index of the most recent rising zero crossing, which is followed by at least `length` samples,
+/// so that periodic signals are displayed at the same phase every frame.
+/// If there is no such zero crossing, the index of the most recent `length` samples is returned.
+pub fn find_waveform_trigger(samples: &[f32], length: usize) -> usize {
+    let latest_start = samples.len().saturating_sub(length);
 
-        let samples_per_frame = Self::get_samples_per_frame();
-        let render_frames_accumulated = mutable_write.sample_buffer.len() / samples_per_frame;
-        let render_frames_over_margin = render_frames_accumulated.saturating_sub(1);
+    (1..=latest_start).rev()
+        .find(|&index| samples[index - 1] < 0.0 && samples[index] >= 0.0)
+        .unwrap_or(latest_start)
+}
 
-        // Get rid of old data, if we lost some frames, or if the results are not being requested.
-        if render_frames_over_margin > 0 {
-            // println!("Skipping {} render frames in FFT calculation.", render_frames_over_margin);
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct GlobalStateAudioLevelDescriptor {
+    input: AudioInputDescriptor,
+    channel: usize,
+    /// Times in milliseconds and the peak decay in dB per second
+    attack: OrderedFloat<f64>,
+    release: OrderedFloat<f64>,
+    peak_hold: OrderedFloat<f64>,
+    peak_decay: OrderedFloat<f64>,
+}
 
-            let samples_to_remove = samples_per_frame * render_frames_over_margin;
-            mutable_write.sample_buffer.drain(0..samples_to_remove);
+impl GlobalStateAudioLevelDescriptor {
+    pub fn new(input: AudioInputDescriptor, channel: usize, meter_parameters: &LevelMeterParameters) -> Self {
+        Self {
+            input,
+            channel,
+            attack: OrderedFloat(meter_parameters.attack),
+            release: OrderedFloat(meter_parameters.release),
+            peak_hold: OrderedFloat(meter_parameters.peak_hold),
+            peak_decay: OrderedFloat(meter_parameters.peak_decay),
         }
+    }
 
-        if !mutable_write.next_batch_scheduled.load(Ordering::SeqCst) {
-            return;
+    fn meter_parameters(&self) -> LevelMeterParameters {
+        LevelMeterParameters {
+            attack: *self.attack,
+            release: *self.release,
+            peak_hold: *self.peak_hold,
+            peak_decay: *self.peak_decay,
         }
+    }
+}
 
-        if render_frames_accumulated > 0 {
-            if mutable_write.window.len() != samples_per_frame {
-                mutable_write.window = Arc::new(this.descriptor.window_function.generate_coefficients(samples_per_frame));
-            }
+#[derive(Default)]
+pub struct GlobalStateAudioLevelMutable {
+    /// Created when the first samples are received, so that the sample rate is known
+    meter: Option<LevelMeter>,
+}
 
-            let window = mutable_write.window.clone();
-            let current_accumulated_samples = mutable_write.sample_buffer.drain(0..samples_per_frame);
-            let mut analysis_result = Self::perform_analysis(current_accumulated_samples, &window);
-
-            // Dampen the result by mixing it with the result from the previous batch
-            if *this.descriptor.dampening_factor_attack > 0.0 || *this.descriptor.dampening_factor_release > 0.0 {
-                if let Some(previous_result) = mutable_write.result.as_ref() {
-                    let dampening_multiplier_attack = this.descriptor.dampening_factor_attack.powf(
-                        Self::render_frames_to_time_elapsed(render_frames_accumulated)
-                    ).clamp(0.0, 1.0) as f32;
-                    let dampening_multiplier_release = this.descriptor.dampening_factor_release.powf(
-                        Self::render_frames_to_time_elapsed(render_frames_accumulated)
-                    ).clamp(0.0, 1.0) as f32;
-
-                    analysis_result.iter_mut()
-                        .zip(previous_result.frequency_spectrum.iter())
-                        .for_each(move |(current, previous)| {
-                            let dampening_multiplier = if *current > *previous {
-                                dampening_multiplier_attack
-                            } else {
-                                dampening_multiplier_release
-                            };
-
-                            *current = dampening_multiplier * *previous + (1.0 - dampening_multiplier) * *current;
-                        })
-                }
-            }
+/// Measures the levels of the audio samples directly, without performing an FFT.
+pub struct GlobalStateAudioLevel {
+    descriptor: GlobalStateAudioLevelDescriptor,
+    /// Kept outside of `mutable`, because OBS holds a lock while calling the capture callback,
+    /// which locks `mutable`, and the same lock is taken when the capture is updated.
+    audio_input: Mutex<Option<Box<dyn AudioInput>>>,
+    mutable: Arc<RwLock<GlobalStateAudioLevelMutable>>,
+}
 
-            let next_batch_number = mutable_write.result.as_ref()
-                .map(|result| result.batch_number + 1).unwrap_or(0);
-                
-            // Compute Mel spectrogram if enabled
-            let mel_spectrum = if this.descriptor.mel_enabled {
-                use mel::MelFilterBank;
-                
-                let sample_rate = ObsAudioInfo::get().unwrap().samples_per_second() as f32;
-                let n_mels = this.descriptor.n_mels;
-                let f_min = *this.descriptor.f_min;
-                let f_max = *this.descriptor.f_max;
-                
-                // Create the Mel filter bank
-                let filter_bank = MelFilterBank::new(
-                    analysis_result.len() * 2 - 2, // Convert back to FFT size
-                    sample_rate,
-                    n_mels,
-                    f_min,
-                    f_max
-                );
-                
-                // Apply the filter bank to the FFT magnitudes
-                let mel_values = filter_bank.apply(&analysis_result);
-                
-                // Apply log compression with small offset to avoid log(0)
-                let mel_db = MelFilterBank::apply_log_compression(&mel_values, 1e-10);
-                
-                // Apply normalization to get values in the range [0, 1]
-                let normalized_mel = MelFilterBank::normalize(&mel_db);
-                
-                Some(Arc::new(normalized_mel))
-            } else {
-                None
-            };
-            
-            mutable_write.result = Some(FFTResult {
-                batch_number: next_batch_number,
-                frequency_spectrum: Arc::new(analysis_result),
-                mel_spectrum,
-            });
-            mutable_write.next_batch_scheduled.swap(false, Ordering::SeqCst);
+impl GlobalStateAudioLevel {
+    /// A problem with the audio input to be displayed to the user, if there is one.
+    fn audio_input_error(&self) -> Option<String> {
+        self.audio_input.lock().unwrap().as_ref()
+            .and_then(|audio_input| audio_input.error().map(Cow::into_owned))
+    }
+
+    fn process_audio_data(this: &Weak<Self>, current_samples: &[f32], sample_rate: f64) {
+        let this = if let Some(this) = Weak::upgrade(this) {
+            this
+        } else {
+            // The audio level component no longer exists, bail.
+            return;
+        };
+
+        let mut mutable_write = this.mutable.write().unwrap();
+
+        if mutable_write.meter.as_ref().map(|meter| meter.sample_rate() != sample_rate).unwrap_or(true) {
+            mutable_write.meter = Some(LevelMeter::new(this.descriptor.meter_parameters(), sample_rate));
         }
+
+        mutable_write.meter.as_mut().unwrap().process(current_samples.iter().copied());
     }
 }
 
-impl GlobalStateComponentType for GlobalStateAudioFFT {
-    type Descriptor = GlobalStateAudioFFTDescriptor;
-    type Result = FFTResult;
+impl GlobalStateComponentType for GlobalStateAudioLevel {
+    type Descriptor = GlobalStateAudioLevelDescriptor;
+    type Result = Levels;
 
     fn create(descriptor: &Self::Descriptor) -> Arc<Self> {
-        let audio = Audio::get();
         let result = Arc::new(Self {
             descriptor: descriptor.clone(),
+            audio_input: Mutex::new(None),
             mutable: Default::default(),
         });
 
-        let audio_output = audio.connect_output(
-            descriptor.mix,
+        let audio_input = descriptor.input.connect(
+            descriptor.channel,
             {
                 let self_cloned = Arc::downgrade(&result);
 
-                Box::new(move |audio_data| {
-                    Self::process_audio_data(&self_cloned, audio_data);
+                Box::new(move |samples, sample_rate| {
+                    Self::process_audio_data(&self_cloned, samples, sample_rate);
                 })
             },
         );
 
-        result.mutable.write().unwrap().audio_output = Some(audio_output);
+        *result.audio_input.lock().unwrap() = Some(audio_input);
 
         result
     }
 
     fn retrieve_result(self: &Arc<Self>) -> Option<Self::Result> {
+        if let Some(audio_input) = self.audio_input.lock().unwrap().as_ref() {
+            audio_input.update();
+        }
+
         let mutable_read = self.mutable.read().unwrap();
 
-        mutable_read.next_batch_scheduled.store(true, Ordering::SeqCst);
-        mutable_read.result.clone()
+        mutable_read.meter.as_ref().map(|meter| meter.levels())
     }
 }
 
@@ -419,53 +778,93 @@ impl<T: GlobalStateComponentType> GlobalStateComponent<T> {
 }
 
 pub struct GlobalState {
+    pub audio_spectra: RwLock<HashMap<GlobalStateAudioSpectrumDescriptor, GlobalStateComponent<GlobalStateAudioSpectrum>>>,
     pub audio_ffts: RwLock<HashMap<GlobalStateAudioFFTDescriptor, GlobalStateComponent<GlobalStateAudioFFT>>>,
+    pub audio_beats: RwLock<HashMap<GlobalStateAudioBeatDescriptor, GlobalStateComponent<GlobalStateAudioBeat>>>,
+    pub audio_waveforms: RwLock<HashMap<GlobalStateAudioWaveformDescriptor, GlobalStateComponent<GlobalStateAudioWaveform>>>,
+    pub audio_levels: RwLock<HashMap<GlobalStateAudioLevelDescriptor, GlobalStateComponent<GlobalStateAudioLevel>>>,
 }
 
 impl Default for GlobalState {
     fn default() -> Self {
         Self {
+            audio_spectra: Default::default(),
             audio_ffts: Default::default(),
+            audio_beats: Default::default(),
+            audio_waveforms: Default::default(),
+            audio_levels: Default::default(),
         }
     }
 }
 
 impl GlobalState {
-    fn request_audio_fft(&self, descriptor: &GlobalStateAudioFFTDescriptor) -> Arc<GlobalStateAudioFFT> {
+    /// Returns the component of the given descriptor, creating it if it does not exist.
+    fn request_component<T>(
+        components: &RwLock<HashMap<T::Descriptor, GlobalStateComponent<T>>>,
+        descriptor: &T::Descriptor,
+    ) -> Arc<T>
+    where T: GlobalStateComponentType,
+          T::Descriptor: Clone + Hash + Eq,
+    {
         {
-            let audio_ffts_read = self.audio_ffts.read().unwrap();
+            let components_read = components.read().unwrap();
 
-            if let Some(audio_fft) = audio_ffts_read.get(descriptor) {
-                return audio_fft.get_component();
+            if let Some(component) = components_read.get(descriptor) {
+                return component.get_component();
             }
         }
 
         {
-            let mut audio_ffts_write = self.audio_ffts.write().unwrap();
+            let mut components_write = components.write().unwrap();
 
-            if let Some(audio_fft) = audio_ffts_write.get(descriptor) {
-                return audio_fft.get_component();
+            if let Some(component) = components_write.get(descriptor) {
+                return component.get_component();
             }
 
             let component_wrapper = GlobalStateComponent::new(descriptor.clone());
             let component = component_wrapper.get_component();
 
-            audio_ffts_write.retain(|_, component| component.try_get_component().is_some());
-            audio_ffts_write.insert(descriptor.clone(), component_wrapper);
+            components_write.retain(|_, component| component.try_get_component().is_some());
+            components_write.insert(descriptor.clone(), component_wrapper);
 
             component
         }
     }
+
+    fn request_audio_spectrum(&self, descriptor: &GlobalStateAudioSpectrumDescriptor) -> Arc<GlobalStateAudioSpectrum> {
+        Self::request_component(&self.audio_spectra, descriptor)
+    }
+
+    fn request_audio_fft(&self, descriptor: &GlobalStateAudioFFTDescriptor) -> Arc<GlobalStateAudioFFT> {
+        Self::request_component(&self.audio_ffts, descriptor)
+    }
+
+    fn request_audio_beat(&self, descriptor: &GlobalStateAudioBeatDescriptor) -> Arc<GlobalStateAudioBeat> {
+        Self::request_component(&self.audio_beats, descriptor)
+    }
+
+    fn request_audio_waveform(&self, descriptor: &GlobalStateAudioWaveformDescriptor) -> Arc<GlobalStateAudioWaveform> {
+        Self::request_component(&self.audio_waveforms, descriptor)
+    }
+
+    fn request_audio_level(&self, descriptor: &GlobalStateAudioLevelDescriptor) -> Arc<GlobalStateAudioLevel> {
+        Self::request_component(&self.audio_levels, descriptor)
+    }
 }
 
 // use crossbeam_channel::{unbounded, Receiver, Sender};
 
+/// How often the shader files are checked for modifications, if automatic reloading is enabled
+const SHADER_WATCHER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 struct Data {
     source: SourceContext,
     effect: Option<PreparedEffect>,
     effect_fallback_blit: GraphicsContextDependentDisabled<GraphicsEffect>,
 
     signal_callback_enable: EnableSignalCallbackHandle,
+    /// Renames of sources, which may be referred to by name in the settings
+    source_renames: SourceRenameSignal,
 
     creation: Instant,
     shown_at: Option<Instant>,
@@ -477,8 +876,17 @@ struct Data {
 
     property_shader: PropertyDescriptor<PropertyDescriptorSpecializationPath>,
     property_shader_reload: PropertyDescriptor<PropertyDescriptorSpecializationButton>,
+    property_include_directories: PropertyDescriptor<PropertyDescriptorSpecializationString>,
+    property_keep_last_working_shader: PropertyDescriptor<PropertyDescriptorSpecializationBool>,
+    property_shader_auto_reload: PropertyDescriptor<PropertyDescriptorSpecializationBool>,
     property_message: PropertyDescriptor<PropertyDescriptorSpecializationString>,
     property_message_display: bool,
+    /// The error which occurred during the last update, if any
+    error_message: Option<String>,
+    /// The message currently displayed in the UI
+    message: String,
+    /// Watches the shader and included files, if automatic reloading is enabled
+    shader_watcher: Option<FileWatcher>,
 
     settings_update_requested: Arc<AtomicBool>,
     shown: bool,
@@ -495,6 +903,7 @@ impl Data {
             signal_callback_enable: source.on_signal_enable(Box::new(move |enabled_new| {
                 enabled_clone.store(enabled_new, Ordering::SeqCst);
             })),
+            source_renames: SourceRenameSignal::connect(),
             source,
             effect: None,
             effect_fallback_blit: {
@@ -542,6 +951,23 @@ impl Data {
                     }),
                 )
             },
+            property_include_directories: PropertyDescriptor {
+                name: CString::new("builtin_ui_include_directories").unwrap(),
+                description: CString::new("Include directories (separated by `;`)").unwrap(),
+                specialization: PropertyDescriptorSpecializationString {
+                    string_type: StringType::Default,
+                }
+            },
+            property_keep_last_working_shader: PropertyDescriptor {
+                name: CString::new("builtin_ui_keep_last_working_shader").unwrap(),
+                description: CString::new("Keep the last working shader, if the shader cannot be loaded (off by default)").unwrap(),
+                specialization: PropertyDescriptorSpecializationBool {},
+            },
+            property_shader_auto_reload: PropertyDescriptor {
+                name: CString::new("builtin_ui_shader_auto_reload").unwrap(),
+                description: CString::new("Reload the shader automatically when it is modified").unwrap(),
+                specialization: PropertyDescriptorSpecializationBool {},
+            },
             property_message: PropertyDescriptor {
                 name: CString::new("builtin_ui_message").unwrap(),
                 description: CString::new("").unwrap(),
@@ -550,11 +976,40 @@ impl Data {
                 }
             },
             property_message_display: false,
+            error_message: None,
+            message: String::new(),
+            shader_watcher: None,
             settings_update_requested,
             shown: false,
             enabled,
         }
     }
+
+    /// Composes the message displayed in the UI from the last error, the preprocessor warnings
+    /// and the messages of effect params.
+    /// Returns `true`, if the message changed and the properties need to be updated.
+    pub fn refresh_message(&mut self, settings: &mut SettingsContext) -> bool {
+        let mut lines = Vec::new();
+
+        lines.extend(self.error_message.clone());
+
+        if let Some(effect) = self.effect.as_ref() {
+            lines.extend(effect.warnings.iter().cloned());
+            lines.extend(effect.params.custom.messages().map(|message| message.into_owned()));
+        }
+
+        let message = lines.join("\n");
+
+        if message == self.message {
+            return false;
+        }
+
+        self.property_message_display = !message.is_empty();
+        settings.set_property_value(&self.property_message, CString::new(message.clone()).unwrap());
+        self.message = message;
+
+        true
+    }
 }
 
 impl Drop for Data {
@@ -597,6 +1052,9 @@ impl GetPropertiesSource<Data> for ShaderFilterPlus {
 
         properties.add_property(&data.property_shader);
         properties.add_property(&data.property_shader_reload);
+        properties.add_property(&data.property_shader_auto_reload);
+        properties.add_property(&data.property_include_directories);
+        properties.add_property(&data.property_keep_last_working_shader);
 
         if data.property_message_display {
             properties.add_property(&data.property_message);
@@ -676,6 +1134,11 @@ impl VideoTickSource<Data> for ShaderFilterPlus {
                 data.source.get_base_width() as i32,
                 data.source.get_base_height() as i32,
             ]);
+            params.date.prepare_value(date_local());
+            params.passes.prepare_values([
+                data.source.get_base_width(),
+                data.source.get_base_height(),
+            ]);
 
             params.custom.prepare_values();
 
@@ -685,6 +1148,31 @@ impl VideoTickSource<Data> for ShaderFilterPlus {
             }
         }
 
+        let source_renames = data.source_renames.take_renames();
+
+        if let Some(effect) = data.effect.as_mut() {
+            let mut settings_changed = false;
+
+            for (previous_name, new_name) in &source_renames {
+                settings_changed |= effect.params.custom.rename_source(settings, previous_name, new_name);
+            }
+
+            if settings_changed {
+                // Reload the settings, so that the audio inputs are keyed by the new names
+                data.settings_update_requested.store(true, Ordering::SeqCst);
+            }
+        }
+
+        if data.refresh_message(settings) {
+            data.source.update_source_properties();
+        }
+
+        if let Some(shader_watcher) = data.shader_watcher.as_mut() {
+            if shader_watcher.poll() {
+                data.settings_update_requested.store(true, Ordering::SeqCst);
+            }
+        }
+
         if data.settings_update_requested.compare_and_swap(true, false, Ordering::SeqCst) {
             data.source.update_source_settings(settings);
         }
@@ -725,19 +1213,43 @@ impl VideoRenderSource<Data> for ShaderFilterPlus {
             return;
         };
 
+        // Render other sources used as textures before the filter rendering begins
+        prepared_effect.params.custom.render(graphics_context);
+
         let effect = &mut prepared_effect.effect.as_enabled_mut(graphics_context);
         let params = &mut prepared_effect.params;
 
+        if params.passes.is_empty() {
+            source.process_filter(
+                effect,
+                (cx, cy),
+                ColorFormatKind::RGBA,
+                GraphicsAllowDirectRendering::NoDirectRendering,
+                |context, _effect| {
+                    params.assign_values(&context);
+                    // image.set_next_sampler(context, sampler);
+                },
+            );
+            return;
+        }
+
+        // Capture the filter input once, so that the sources before the filter are not rendered again for each pass.
+        // The values are assigned to the effect while capturing, they are kept for drawing the passes.
+        if !params.passes.begin_capture([cx, cy]) {
+            return;
+        }
+
         source.process_filter(
-            effect,
+            &mut data.effect_fallback_blit.as_enabled_mut(graphics_context),
             (cx, cy),
             ColorFormatKind::RGBA,
             GraphicsAllowDirectRendering::NoDirectRendering,
             |context, _effect| {
                 params.assign_values(&context);
-                // image.set_next_sampler(context, sampler);
             },
         );
+        params.passes.end_capture();
+        params.passes.render(effect.as_ptr(), [cx, cy], graphics_context);
     }
 }
 
@@ -761,22 +1273,40 @@ impl UpdateSource<Data> for ShaderFilterPlus {
                 throw!("Please specify the shader source file.");
             }
 
-            let mut shader_file = File::open(&shader_path)
-                .map_err(|_| {
-                    if let Some(effect) = data.effect.take() {
-                        let graphics_context = GraphicsContext::enter()
-                            .expect("Could not enter a graphics context.");
-
-                        effect.enable_and_drop(&graphics_context);
-                    }
-
-                    format!("Shader not found at the specified path: {:?}", &shader_path)
-                })?;
-            let shader_source = {
-                let mut shader_source = String::new();
-                shader_file.read_to_string(&mut shader_source).expect("Could not read the shader at the given path.");
-                shader_source
+            let auto_reload = settings.get_property_value(&data.property_shader_auto_reload, &false);
+
+            // Watch the shader file until the included files are known, so that the shader is
+            // reloaded once it is fixed, even if it cannot be loaded now.
+            data.shader_watcher = if auto_reload {
+                Some(FileWatcher::new(Some(shader_path.clone()), SHADER_WATCHER_POLL_INTERVAL))
+            } else {
+                None
+            };
+
+            if !shader_path.is_file() {
+                throw!(format!("Shader not found at the specified path: {:?}", &shader_path));
+            }
+
+            let include_directories = parse_include_directories(
+                &settings.get_property_value(&data.property_include_directories, &CString::default())
+                    .to_string_lossy()
+            );
+            // Watch the files resolved so far, if the includes cannot be expanded,
+            // so that the shader is reloaded once the missing file is created.
+            let (shader, files) = match expand_includes(&shader_path, &include_directories) {
+                Ok(shader) => {
+                    let files = shader.files.clone();
+                    (Ok(shader), files)
+                },
+                Err(error) => (Err(error.message), error.files),
             };
+
+            if auto_reload {
+                data.shader_watcher = Some(FileWatcher::new(files, SHADER_WATCHER_POLL_INTERVAL));
+            }
+
+            let shader = shader?;
+            let shader_source = shader.source.clone();
             let old_shader_source = data.effect.as_ref().map(|old_effect| {
                 old_effect.shader_source.clone()
             });
@@ -785,6 +1315,7 @@ impl UpdateSource<Data> for ShaderFilterPlus {
                 // Only update the params, if the shader stayed the same
                 let effect = data.effect.as_mut().unwrap();
                 effect.params.reload_settings(&mut settings);
+                data.error_message = None;
                 return;
             }
 
@@ -792,7 +1323,7 @@ impl UpdateSource<Data> for ShaderFilterPlus {
 
             // If shader source changed, create a new effect and request to update properties
             let graphics_context = GraphicsContext::enter().unwrap();
-            let (effect, preprocess_result) = PreparedEffect::create_effect(&shader_path, &shader_source, &graphics_context)?;
+            let (effect, preprocess_result) = PreparedEffect::create_effect(&shader_path, &shader, &graphics_context)?;
             let mut builtin_param_names = vec!["ViewProj", "image"];
 
             macro_rules! builtin_effect {
@@ -822,10 +1353,18 @@ impl UpdateSource<Data> for ShaderFilterPlus {
                 elapsed_time_since_enabled: builtin_effect!("builtin_elapsed_time_since_enabled"),
                 elapsed_time_since_enabled_previous: builtin_effect!("builtin_elapsed_time_since_enabled_previous"),
                 uv_size: builtin_effect!("builtin_uv_size"),
+                date: builtin_effect!("builtin_date"),
+                passes: Default::default(),
                 custom: Default::default(),
             };
+            // Only declared by the effect template, if there are any passes
+            let pass_index = if preprocess_result.passes().is_empty() {
+                None
+            } else {
+                Some(builtin_effect!("builtin_pass"))
+            };
 
-            let custom_params = effect.params_iter()
+            let mut custom_params = effect.params_iter()
                 .filter(|item| {
                     !builtin_param_names.contains(&item.name())
                 })
@@ -835,11 +1374,24 @@ impl UpdateSource<Data> for ShaderFilterPlus {
                 })
                 .collect::<HashMap<_, _>>();
 
-            params.custom = EffectParamsCustom::from(custom_params, settings, &preprocess_result)?;
+            let shader_directory = shader_path.parent()
+                .map(|directory| directory.to_path_buf())
+                .unwrap_or_default();
+
+            params.passes = EffectPasses::from(pass_index, preprocess_result.passes(), &mut custom_params)?;
+            params.custom = EffectParamsCustom::from(custom_params, &shader_directory, settings, &preprocess_result)?;
+
+            // Display preprocessor warnings, if there are any
+            let mut diagnostics = preprocess_result.diagnostics().to_vec();
+            diagnostics.extend(preprocess_result.unused_definition_diagnostics());
+            diagnostics.sort_by_key(|diagnostic| diagnostic.line);
 
             let effect = PreparedEffect {
                 effect: effect.disable(),
                 shader_source: shader_source.clone(),
+                warnings: diagnostics.iter()
+                    .map(|diagnostic| diagnostic.format(&shader.source_map))
+                    .collect(),
                 params,
             };
 
@@ -850,9 +1402,8 @@ impl UpdateSource<Data> for ShaderFilterPlus {
                 old_effect.enable_and_drop(&graphics_context);
             }
 
-            data.property_message_display = false;
-
-            settings.set_property_value(&data.property_message, CString::new("").unwrap());
+            data.error_message = None;
+            data.refresh_message(settings);
             data.source.update_source_properties();
         };
 
@@ -862,12 +1413,21 @@ impl UpdateSource<Data> for ShaderFilterPlus {
             let (data, settings) = context.data_settings_mut();
 
             if let Some(data) = data.as_mut() {
-                data.property_message_display = true;
+                let keep_last_working_shader = settings.get_property_value(&data.property_keep_last_working_shader, &false);
+                let mut error_message = error_message.into_owned();
+
+                if keep_last_working_shader && data.effect.is_some() {
+                    error_message.push_str("\nThe last working shader is still in use.");
+                } else if let Some(effect) = data.effect.take() {
+                    // Fall back to passing the source through unchanged
+                    let graphics_context = GraphicsContext::enter()
+                        .expect("Could not enter a graphics context.");
 
-                settings.set_property_value(
-                    &data.property_message,
-                    CString::new(error_message.as_ref()).unwrap(),
-                );
+                    effect.enable_and_drop(&graphics_context);
+                }
+
+                data.error_message = Some(error_message);
+                data.refresh_message(settings);
                 data.source.update_source_properties();
             }
         }
@@ -969,4 +1529,32 @@ mod tests {
         let log_mel = MelFilterBank::apply_log_compression(&mel_output, 1e-10);
         assert_eq!(log_mel.len(), n_mels);
     }
+
+    #[test]
+    fn test_find_waveform_trigger() {
+        let samples = [0.5, -0.5, 0.5, -0.5, -0.5, 0.5, 0.5, 0.5];
+
+        // The most recent crossing at index 5 is not followed by enough samples
+        assert_eq!(find_waveform_trigger(&samples, 4), 2);
+        assert_eq!(find_waveform_trigger(&samples, 3), 5);
+        // No crossing, the most recent samples are used
+        assert_eq!(find_waveform_trigger(&[1.0; 8], 4), 4);
+        assert_eq!(find_waveform_trigger(&[1.0; 2], 4), 0);
+    }
+
+    #[test]
+    fn test_date() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        let offset = |offset_seconds| FixedOffset::east_opt(offset_seconds).unwrap();
+        let epoch = Utc.timestamp_opt(0, 0).unwrap();
+        let leap_day_noon = Utc.timestamp_opt(1_582_977_600, 0).unwrap();
+
+        assert_eq!(date(&epoch), [1970.0, 0.0, 1.0, 0.0]);
+        assert_eq!(date(&leap_day_noon), [2020.0, 1.0, 29.0, 43200.0]);
+        assert_eq!(date(&(leap_day_noon + chrono::Duration::days(1))), [2020.0, 2.0, 1.0, 43200.0]);
+        // Time zones east and west of UTC
+        assert_eq!(date(&leap_day_noon.with_timezone(&offset(13 * 3600))), [2020.0, 2.0, 1.0, 3600.0]);
+        assert_eq!(date(&epoch.with_timezone(&offset(-3600))), [1969.0, 11.0, 31.0, 82800.0]);
+    }
 }
@@ -0,0 +1,117 @@
+use std::f64::consts::PI;
+
+/// A second order IIR filter in direct form I.
+#[derive(Clone, Debug)]
+pub struct Biquad {
+    b: [f64; 3],
+    /// Feedback coefficients, `a0` is normalized to `1`
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    pub fn coefficients(&self) -> ([f64; 3], [f64; 2]) {
+        (self.b, self.a)
+    }
+
+    pub fn process(&mut self, sample: f64) -> f64 {
+        let result = self.b[0] * sample + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+
+        self.x = [sample, self.x[0]];
+        self.y = [result, self.y[0]];
+
+        result
+    }
+}
+
+/// The K-weighting filter of ITU-R BS.1770, a high shelf modelling the acoustic effect of the head,
+/// followed by a high pass. The coefficients are derived for any sample rate.
+#[derive(Clone, Debug)]
+pub struct KWeightingFilter {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeightingFilter {
+    pub fn new(sample_rate: f64) -> Self {
+        let shelf = {
+            let f0 = 1681.974450955533;
+            let gain = 3.999843853973347;
+            let q = 0.7071752369554196;
+            let k = (PI * f0 / sample_rate).tan();
+            let vh = 10f64.powf(gain / 20.0);
+            let vb = vh.powf(0.4996667741545416);
+            let a0 = 1.0 + k / q + k * k;
+
+            Biquad::new(
+                [
+                    (vh + vb * k / q + k * k) / a0,
+                    2.0 * (k * k - vh) / a0,
+                    (vh - vb * k / q + k * k) / a0,
+                ],
+                [
+                    2.0 * (k * k - 1.0) / a0,
+                    (1.0 - k / q + k * k) / a0,
+                ],
+            )
+        };
+        let high_pass = {
+            let f0 = 38.13547087602444;
+            let q = 0.5003270373238773;
+            let k = (PI * f0 / sample_rate).tan();
+            let a0 = 1.0 + k / q + k * k;
+
+            Biquad::new(
+                [1.0, -2.0, 1.0],
+                [
+                    2.0 * (k * k - 1.0) / a0,
+                    (1.0 - k / q + k * k) / a0,
+                ],
+            )
+        };
+
+        Self {
+            shelf,
+            high_pass,
+        }
+    }
+
+    pub fn process(&mut self, sample: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(sample))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-8, "{} != {}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_coefficients_48khz() {
+        // Reference coefficients from ITU-R BS.1770-4
+        let filter = KWeightingFilter::new(48000.0);
+        let (shelf_b, shelf_a) = filter.shelf.coefficients();
+        let (high_pass_b, high_pass_a) = filter.high_pass.coefficients();
+
+        assert_close(&shelf_b, &[1.53512485958697, -2.69169618940638, 1.19839281085285]);
+        assert_close(&shelf_a, &[-1.69065929318241, 0.73248077421585]);
+        assert_close(&high_pass_b, &[1.0, -2.0, 1.0]);
+        assert_close(&high_pass_a, &[-1.99004745483398, 0.99007225036621]);
+    }
+}
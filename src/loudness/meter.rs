@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use super::k_weighting::KWeightingFilter;
+use super::MIN_DB;
+
+/// The time constant in milliseconds the mean square is integrated over, before attack and release are applied.
+const RMS_INTEGRATION_TIME: f64 = 50.0;
+/// Loudness is measured in blocks of this many seconds.
+const BLOCK_DURATION: f64 = 0.1;
+/// The number of blocks of the momentary loudness, 400 ms
+const MOMENTARY_BLOCKS: usize = 4;
+/// The number of blocks of the short-term loudness, 3 s
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Parameters for configuring the ballistics of a level meter, times are given in milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelMeterParameters {
+    /// The time constant of the RMS level, when the level rises
+    pub attack: f64,
+    /// The time constant of the RMS level, when the level falls
+    pub release: f64,
+    /// How long the peak level is held, before it starts to decay
+    pub peak_hold: f64,
+    /// How fast the peak level decays in dB per second
+    pub peak_decay: f64,
+}
+
+/// The levels measured by a level meter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Levels {
+    pub rms: f32,
+    pub peak: f32,
+    /// The K-weighted loudness of the last 3 seconds in LUFS
+    pub short_term_loudness: f32,
+    /// The K-weighted loudness of the last 400 ms in LUFS
+    pub momentary_loudness: f32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            rms: 0.0,
+            peak: 0.0,
+            short_term_loudness: MIN_DB,
+            momentary_loudness: MIN_DB,
+        }
+    }
+}
+
+/// Converts a time constant in milliseconds to the coefficient of a one-pole smoothing filter.
+fn smoothing_coefficient(time_ms: f64, sample_rate: f64) -> f64 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1000.0 / (time_ms * sample_rate)).exp()
+    }
+}
+
+/// Computes the loudness in LUFS from the mean square of K-weighted samples.
+fn loudness(mean_square: f64) -> f32 {
+    if mean_square <= 0.0 {
+        MIN_DB
+    } else {
+        ((-0.691 + 10.0 * mean_square.log10()) as f32).max(MIN_DB)
+    }
+}
+
+/// Measures the RMS, peak and loudness of a single channel.
+pub struct LevelMeter {
+    parameters: LevelMeterParameters,
+    sample_rate: f64,
+    integration_coefficient: f64,
+    attack_coefficient: f64,
+    release_coefficient: f64,
+    /// The peak level decays by this factor per sample
+    peak_decay_factor: f64,
+    peak_hold_samples: usize,
+    mean_square: f64,
+    rms: f64,
+    peak: f64,
+    samples_since_peak: usize,
+    k_weighting: KWeightingFilter,
+    block_len: usize,
+    /// The sum of the squared K-weighted samples of the current block and the number of its samples
+    current_block: (f64, usize),
+    /// The mean squares of the most recent complete blocks
+    blocks: VecDeque<f64>,
+}
+
+impl LevelMeter {
+    pub fn new(parameters: LevelMeterParameters, sample_rate: f64) -> Self {
+        Self {
+            integration_coefficient: smoothing_coefficient(RMS_INTEGRATION_TIME, sample_rate),
+            attack_coefficient: smoothing_coefficient(parameters.attack, sample_rate),
+            release_coefficient: smoothing_coefficient(parameters.release, sample_rate),
+            peak_decay_factor: 10f64.powf(-parameters.peak_decay.max(0.0) / (20.0 * sample_rate)),
+            peak_hold_samples: (parameters.peak_hold.max(0.0) * sample_rate / 1000.0) as usize,
+            parameters,
+            sample_rate,
+            mean_square: 0.0,
+            rms: 0.0,
+            peak: 0.0,
+            samples_since_peak: 0,
+            k_weighting: KWeightingFilter::new(sample_rate),
+            block_len: ((BLOCK_DURATION * sample_rate).round() as usize).max(1),
+            current_block: (0.0, 0),
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+        }
+    }
+
+    pub fn parameters(&self) -> &LevelMeterParameters {
+        &self.parameters
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn process(&mut self, samples: impl IntoIterator<Item=f32>) {
+        for sample in samples {
+            let sample = sample as f64;
+
+            self.mean_square = self.integration_coefficient * self.mean_square
+                + (1.0 - self.integration_coefficient) * sample * sample;
+
+            let rms = self.mean_square.sqrt();
+            let coefficient = if rms > self.rms {
+                self.attack_coefficient
+            } else {
+                self.release_coefficient
+            };
+
+            self.rms = coefficient * self.rms + (1.0 - coefficient) * rms;
+
+            if sample.abs() >= self.peak {
+                self.peak = sample.abs();
+                self.samples_since_peak = 0;
+            } else if self.samples_since_peak >= self.peak_hold_samples {
+                self.peak *= self.peak_decay_factor;
+            } else {
+                self.samples_since_peak += 1;
+            }
+
+            let weighted = self.k_weighting.process(sample);
+
+            self.current_block.0 += weighted * weighted;
+            self.current_block.1 += 1;
+
+            if self.current_block.1 >= self.block_len {
+                if self.blocks.len() >= SHORT_TERM_BLOCKS {
+                    self.blocks.pop_front();
+                }
+
+                self.blocks.push_back(self.current_block.0 / self.current_block.1 as f64);
+                self.current_block = (0.0, 0);
+            }
+        }
+    }
+
+    /// The mean square of the most recent `count` complete blocks, or fewer, if not enough were measured yet.
+    fn blocks_mean_square(&self, count: usize) -> f64 {
+        let count = count.min(self.blocks.len());
+
+        if count == 0 {
+            return 0.0;
+        }
+
+        self.blocks.iter().rev().take(count).sum::<f64>() / count as f64
+    }
+
+    pub fn levels(&self) -> Levels {
+        Levels {
+            rms: self.rms as f32,
+            peak: self.peak as f32,
+            short_term_loudness: loudness(self.blocks_mean_square(SHORT_TERM_BLOCKS)),
+            momentary_loudness: loudness(self.blocks_mean_square(MOMENTARY_BLOCKS)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loudness::amplitude_to_db;
+    use crate::test_util::{sine, SAMPLE_RATE};
+
+    fn parameters() -> LevelMeterParameters {
+        LevelMeterParameters {
+            attack: 10.0,
+            release: 300.0,
+            peak_hold: 1000.0,
+            peak_decay: 20.0,
+        }
+    }
+
+    #[test]
+    fn test_sine_levels() {
+        let mut meter = LevelMeter::new(parameters(), SAMPLE_RATE);
+
+        meter.process(sine(997.0, 0.5, (SAMPLE_RATE * 4.0) as usize));
+
+        let levels = meter.levels();
+
+        assert!((levels.rms - 0.5 / 2f32.sqrt()).abs() < 0.01, "{:?}", levels);
+        assert!((levels.peak - 0.5).abs() < 1e-3, "{:?}", levels);
+        // A full scale 1 kHz sine measures -3.01 LUFS, halving the amplitude lowers it by 6.02 dB
+        assert!((levels.short_term_loudness - -9.03).abs() < 0.1, "{:?}", levels);
+        assert!((levels.momentary_loudness - -9.03).abs() < 0.1, "{:?}", levels);
+        assert!((amplitude_to_db(levels.peak) - -6.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_peak_hold_and_decay() {
+        let mut meter = LevelMeter::new(parameters(), SAMPLE_RATE);
+
+        meter.process(std::iter::once(1.0));
+        meter.process(vec![0.0; (SAMPLE_RATE * 0.5) as usize]);
+        assert_eq!(meter.levels().peak, 1.0);
+
+        // Held for 1 s, then decayed by 20 dB/s for 1 s
+        meter.process(vec![0.0; (SAMPLE_RATE * 1.5) as usize]);
+        assert!((amplitude_to_db(meter.levels().peak) - -20.0).abs() < 0.1, "{:?}", meter.levels());
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut meter = LevelMeter::new(parameters(), SAMPLE_RATE);
+
+        meter.process(vec![0.0; SAMPLE_RATE as usize]);
+
+        assert_eq!(meter.levels(), Levels::default());
+    }
+}
@@ -0,0 +1,41 @@
+mod k_weighting;
+mod meter;
+
+// Re-export the public API
+pub use k_weighting::{Biquad, KWeightingFilter};
+pub use meter::{LevelMeter, LevelMeterParameters, Levels};
+
+/// The level in dB reported for silence, instead of negative infinity
+pub const MIN_DB: f32 = -100.0;
+
+/// Converts a linear amplitude to dBFS, no lower than `MIN_DB`.
+pub fn amplitude_to_db(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        MIN_DB
+    } else {
+        (20.0 * amplitude.log10()).max(MIN_DB)
+    }
+}
+
+/// Converts a level in dB to a linear amplitude, where `MIN_DB` is mapped to `0`.
+pub fn db_to_amplitude(db: f32) -> f32 {
+    if db <= MIN_DB {
+        0.0
+    } else {
+        10f32.powf(db / 20.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_db_conversion() {
+        assert_eq!(amplitude_to_db(1.0), 0.0);
+        assert_eq!(amplitude_to_db(0.0), MIN_DB);
+        assert!((amplitude_to_db(0.1) - -20.0).abs() < 1e-4);
+        assert!((db_to_amplitude(-20.0) - 0.1).abs() < 1e-6);
+        assert_eq!(db_to_amplitude(MIN_DB), 0.0);
+    }
+}
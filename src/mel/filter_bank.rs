@@ -1,28 +1,50 @@
-use super::utils::{hz_to_mel, mel_to_hz, linspace};
+use super::utils::{linspace, MelFormula};
+use super::MelParameters;
+
+/// A triangular filter, which is zero outside of the bins it covers
+#[derive(Debug, Clone, PartialEq)]
+struct MelFilter {
+    /// The first bin covered by the filter
+    start_bin: usize,
+    /// The coefficients of the covered bins, starting at `start_bin`
+    coefficients: Vec<f32>,
+}
 
 /// Struct representing the Mel Filter Bank
 pub struct MelFilterBank {
-    filters: Vec<Vec<f32>>, // Each filter is a vector of coefficients
+    filters: Vec<MelFilter>,
+    /// The frequencies in Hz, where each filter peaks
+    center_frequencies: Vec<f32>,
     fft_size: usize,
     sample_rate: f32,
-    n_mels: usize,
-    f_min: f32,
-    f_max: f32,
+    parameters: MelParameters,
 }
 
 impl MelFilterBank {
-    /// Create a new MelFilterBank and compute triangular filters
+    /// Create a new MelFilterBank and compute triangular filters, using the HTK formula without normalization
     pub fn new(fft_size: usize, sample_rate: f32, n_mels: usize, f_min: f32, f_max: f32) -> Self {
+        Self::from_parameters(fft_size, sample_rate, MelParameters {
+            n_mels,
+            f_min,
+            f_max,
+            formula: MelFormula::Htk,
+            area_normalization: false,
+        })
+    }
+
+    /// Create a new MelFilterBank and compute triangular filters
+    pub fn from_parameters(fft_size: usize, sample_rate: f32, parameters: MelParameters) -> Self {
+        let MelParameters { n_mels, f_min, f_max, formula, area_normalization } = parameters;
         // Number of FFT bins (only need non-redundant bins)
         let n_fft_bins = fft_size / 2 + 1;
 
         // Compute Mel boundaries for f_min and f_max
-        let mel_min = hz_to_mel(f_min);
-        let mel_max = hz_to_mel(f_max);
+        let mel_min = formula.hz_to_mel(f_min);
+        let mel_max = formula.hz_to_mel(f_max);
         // Compute n_mels + 2 points in Mel scale (including the boundaries)
         let mel_points = linspace(mel_min, mel_max, n_mels + 2);
         // Convert Mel points back to Hz
-        let hz_points: Vec<f32> = mel_points.iter().map(|&m| mel_to_hz(m)).collect();
+        let hz_points: Vec<f32> = mel_points.iter().map(|&m| formula.mel_to_hz(m)).collect();
         // Map each Hz point to the corresponding FFT bin
         let bin: Vec<usize> = hz_points.iter()
             .map(|&hz| ((fft_size as f32 + 1.0) * hz / sample_rate).floor() as usize)
@@ -30,49 +52,66 @@ impl MelFilterBank {
 
         let mut filters = Vec::with_capacity(n_mels);
         for i in 0..n_mels {
-            let mut filter = vec![0.0; n_fft_bins];
-            let bin_left = bin[i];
-            let bin_center = bin[i + 1];
-            let bin_right = bin[i + 2];
+            // Bins beyond the Nyquist frequency are not covered
+            let bin_left = bin[i].min(n_fft_bins);
+            let bin_center = bin[i + 1].min(n_fft_bins).max(bin_left);
+            let bin_right = bin[i + 2].min(n_fft_bins).max(bin_center);
+            let mut coefficients = vec![0.0; bin_right - bin_left];
 
             // Rising slope of the triangular filter
             for j in bin_left..bin_center {
-                if bin_center != bin_left {
-                    filter[j] = (j - bin_left) as f32 / (bin_center - bin_left) as f32;
-                }
+                coefficients[j - bin_left] = (j - bin_left) as f32 / (bin_center - bin_left) as f32;
             }
             // Falling slope of the triangular filter
             for j in bin_center..bin_right {
-                if bin_right != bin_center {
-                    filter[j] = (bin_right - j) as f32 / (bin_right - bin_center) as f32;
-                }
+                coefficients[j - bin_left] = (bin_right - j) as f32 / (bin_right - bin_center) as f32;
+            }
+            // Scale the filter by the inverse of its width in Hz, so that the filters have the same area
+            if area_normalization && hz_points[i + 2] > hz_points[i] {
+                let normalization = 2.0 / (hz_points[i + 2] - hz_points[i]);
+
+                coefficients.iter_mut().for_each(|coefficient| *coefficient *= normalization);
             }
-            filters.push(filter);
+            filters.push(MelFilter {
+                start_bin: bin_left,
+                coefficients,
+            });
         }
 
         MelFilterBank {
             filters,
+            center_frequencies: hz_points[1..=n_mels].to_vec(),
             fft_size,
             sample_rate,
-            n_mels,
-            f_min,
-            f_max,
+            parameters,
         }
     }
 
-    /// Apply the filter bank to a slice of FFT magnitudes
+    /// The FFT size the filter bank was created for
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn center_frequencies(&self) -> &[f32] {
+        &self.center_frequencies
+    }
+
+    pub fn parameters(&self) -> &MelParameters {
+        &self.parameters
+    }
+
+    /// Apply the filter bank to a slice of FFT magnitudes, only the bins covered by each filter are visited
     pub fn apply(&self, fft_magnitudes: &[f32]) -> Vec<f32> {
-        let mut mel_output = vec![0.0; self.n_mels];
-        let n_fft_bins = fft_magnitudes.len();
-        for (i, filter) in self.filters.iter().enumerate() {
-            let mut sum = 0.0;
-            let bins = n_fft_bins.min(filter.len());
-            for j in 0..bins {
-                sum += filter[j] * fft_magnitudes[j];
-            }
-            mel_output[i] = sum;
-        }
-        mel_output
+        self.filters.iter().map(|filter| {
+            filter.coefficients.iter()
+                .zip(fft_magnitudes.iter().skip(filter.start_bin))
+                .map(|(coefficient, magnitude)| coefficient * magnitude)
+                .sum()
+        }).collect()
     }
     
     /// Apply log compression to mel values
@@ -81,35 +120,6 @@ impl MelFilterBank {
             .map(|&x| 10.0 * (x + offset).log10())
             .collect()
     }
-    
-    /// Normalize MEL values to the range [0.0, 1.0]
-    /// 
-    /// This helps ensure consistent signal strength across different audio inputs
-    /// by mapping the dynamic range of the MEL values to a standardized range.
-    pub fn normalize(mel_values: &[f32]) -> Vec<f32> {
-        if mel_values.is_empty() {
-            return vec![];
-        }
-        
-        // Find the min and max values
-        let mut min_val = mel_values[0];
-        let mut max_val = mel_values[0];
-        
-        for &val in mel_values.iter() {
-            min_val = min_val.min(val);
-            max_val = max_val.max(val);
-        }
-        
-        // If range is zero, return values scaled to 0.5 to avoid division by zero
-        if (max_val - min_val).abs() < 1e-6 {
-            return mel_values.iter().map(|_| 0.5).collect();
-        }
-        
-        // Normalize to [0, 1] range
-        mel_values.iter()
-            .map(|&x| (x - min_val) / (max_val - min_val))
-            .collect()
-    }
 }
 
 #[cfg(test)]
@@ -135,6 +145,56 @@ mod tests {
         assert!(sum > 0.0);
     }
     
+    #[test]
+    fn test_sparse_filters() {
+        let fft_size = 1024;
+        let filterbank = MelFilterBank::new(fft_size, 48000.0, 40, 20.0, 20000.0);
+
+        for (i, filter) in filterbank.filters.iter().enumerate() {
+            // Each filter covers the bins up to the peak of the next filter
+            let next_start_bin = filterbank.filters.get(i + 2).map(|filter| filter.start_bin);
+
+            assert!(filter.start_bin + filter.coefficients.len() <= fft_size / 2 + 1);
+            assert!(next_start_bin.map(|next| filter.start_bin + filter.coefficients.len() <= next).unwrap_or(true));
+        }
+
+        // Applying the filter bank to an impulse yields the coefficients of the bin
+        let mut fft_magnitudes = vec![0.0; fft_size / 2 + 1];
+        fft_magnitudes[100] = 1.0;
+
+        let mel_output = filterbank.apply(&fft_magnitudes);
+        let expected = filterbank.filters.iter().map(|filter| {
+            100usize.checked_sub(filter.start_bin)
+                .and_then(|index| filter.coefficients.get(index))
+                .copied()
+                .unwrap_or(0.0)
+        }).collect::<Vec<_>>();
+
+        assert_eq!(mel_output, expected);
+        assert!((mel_output.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_area_normalization() {
+        let parameters = MelParameters {
+            n_mels: 20,
+            f_min: 0.0,
+            f_max: 8000.0,
+            formula: MelFormula::Slaney,
+            area_normalization: true,
+        };
+        let filterbank = MelFilterBank::from_parameters(512, 16000.0, parameters);
+        // The bins are 31.25 Hz apart
+        let areas = filterbank.filters.iter()
+            .map(|filter| filter.coefficients.iter().sum::<f32>() * 31.25)
+            .collect::<Vec<_>>();
+
+        // Wide filters have approximately the same area, narrow ones are distorted by the bin quantization
+        for area in &areas[10..] {
+            assert!((area - 1.0).abs() < 0.1, "{:?}", areas);
+        }
+    }
+
     #[test]
     fn test_log_compression() {
         let values = vec![0.1, 1.0, 10.0];
@@ -0,0 +1,85 @@
+use std::f32::consts::PI;
+use super::MelFilterBank;
+
+/// Compute the orthonormal DCT-II of the values, keeping only the first `n_coefficients` coefficients
+pub fn dct(values: &[f32], n_coefficients: usize) -> Vec<f32> {
+    let n = values.len();
+
+    if n == 0 {
+        return vec![0.0; n_coefficients];
+    }
+
+    (0..n_coefficients).map(|k| {
+        let sum: f32 = values.iter().enumerate()
+            .map(|(i, value)| value * (PI * k as f32 * (2 * i + 1) as f32 / (2 * n) as f32).cos())
+            .sum();
+        let normalization = if k == 0 { (1.0 / n as f32).sqrt() } else { (2.0 / n as f32).sqrt() };
+
+        sum * normalization
+    }).collect()
+}
+
+/// Compute the Mel-frequency cepstral coefficients of a spectrum of FFT magnitudes
+///
+/// The filter bank is applied to the power spectrum, the DCT is performed on the Mel bands in dB.
+pub fn mfcc(filter_bank: &MelFilterBank, fft_magnitudes: &[f32], n_coefficients: usize) -> Vec<f32> {
+    let power_spectrum = fft_magnitudes.iter().map(|magnitude| magnitude * magnitude).collect::<Vec<_>>();
+    let mel_power = filter_bank.apply(&power_spectrum);
+    let log_mel = MelFilterBank::apply_log_compression(&mel_power, 1e-10);
+
+    dct(&log_mel, n_coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FFT_SIZE: usize = 1024;
+    const SAMPLE_RATE: f32 = 16000.0;
+
+    /// The magnitudes of a sine at the center of the given bin, leaking into the neighbouring bins, above a noise floor
+    fn sine_spectrum(bin: usize) -> Vec<f32> {
+        let mut magnitudes = vec![0.001; FFT_SIZE / 2];
+
+        magnitudes[bin - 1] = 0.25;
+        magnitudes[bin] = 0.5;
+        magnitudes[bin + 1] = 0.25;
+        magnitudes
+    }
+
+    #[test]
+    fn test_dct() {
+        // A constant signal only has a DC component
+        let coefficients = dct(&[2.0; 8], 4);
+
+        assert!((coefficients[0] - 2.0 * 8f32.sqrt()).abs() < 1e-4);
+        assert!(coefficients[1..].iter().all(|coefficient| coefficient.abs() < 1e-4));
+
+        // The orthonormal DCT preserves the energy, when all coefficients are kept
+        let values = [1.0, -2.0, 3.0, 0.5, 0.0];
+        let coefficients = dct(&values, values.len());
+        let energy = |values: &[f32]| values.iter().map(|value| value * value).sum::<f32>();
+
+        assert!((energy(&values) - energy(&coefficients)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_mfcc_of_sines() {
+        let filter_bank = MelFilterBank::new(FFT_SIZE, SAMPLE_RATE, 40, 0.0, 8000.0);
+        let low = mfcc(&filter_bank, &sine_spectrum(16), 13);
+        let high = mfcc(&filter_bank, &sine_spectrum(200), 13);
+        let louder_low = mfcc(&filter_bank, &sine_spectrum(16).iter().map(|value| value * 10.0).collect::<Vec<_>>(), 13);
+
+        assert_eq!(low.len(), 13);
+        assert!(low.iter().chain(&high).all(|coefficient| coefficient.is_finite()));
+
+        // Sines of different frequencies have a different spectral shape
+        let distance = low.iter().zip(&high).map(|(a, b)| (a - b).abs()).sum::<f32>();
+
+        assert!(distance > 100.0);
+
+        // Increasing the volume by 20 dB only shifts the dB values, which is captured by the DC component
+        assert!((louder_low[0] - low[0] - 20.0 * 40f32.sqrt()).abs() < 1e-2);
+        assert!(louder_low[1..].iter().zip(&low[1..]).all(|(a, b)| (a - b).abs() < 1e-2));
+    }
+}
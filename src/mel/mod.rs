@@ -1,13 +1,19 @@
 mod filter_bank;
+mod mfcc;
 mod utils;
 
 // Re-export the public API
 pub use filter_bank::MelFilterBank;
-pub use utils::{hz_to_mel, mel_to_hz, linspace};
+pub use mfcc::{dct, mfcc};
+pub use utils::{hz_to_mel, mel_to_hz, linspace, MelFormula};
 
 /// Parameters for configuring Mel spectrogram generation
+#[derive(Clone, Debug, PartialEq)]
 pub struct MelParameters {
     pub n_mels: usize,
     pub f_min: f32,
     pub f_max: f32,
+    pub formula: MelFormula,
+    /// Whether each filter is normalized by its width, so that filters have the same area
+    pub area_normalization: bool,
 } 
\ No newline at end of file
@@ -1,13 +1,77 @@
 use std::f32;
 
-/// Convert frequency (Hz) to Mel scale
+/// The formula used to convert between frequencies and the Mel scale
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MelFormula {
+    /// `2595 log10(1 + f / 700)`, as used by HTK
+    Htk,
+    /// Linear below 1 kHz and logarithmic above, as used by the Auditory Toolbox of Slaney and librosa
+    Slaney,
+}
+
+impl MelFormula {
+    /// Names of the formulas as used in properties, along with their descriptions.
+    pub const ITEMS: &'static [(&'static str, &'static str)] = &[
+        ("htk", "HTK"),
+        ("slaney", "Slaney"),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "htk" => MelFormula::Htk,
+            "slaney" => MelFormula::Slaney,
+            _ => return None,
+        })
+    }
+
+    /// Convert frequency (Hz) to Mel scale
+    pub fn hz_to_mel(self, f: f32) -> f32 {
+        match self {
+            MelFormula::Htk => 2595.0 * (1.0 + f / 700.0).log10(),
+            MelFormula::Slaney => {
+                if f < SLANEY_MIN_LOG_HZ {
+                    f / SLANEY_HZ_PER_MEL
+                } else {
+                    SLANEY_MIN_LOG_MEL + (f / SLANEY_MIN_LOG_HZ).ln() / slaney_log_step()
+                }
+            },
+        }
+    }
+
+    /// Convert Mel scale value to frequency (Hz)
+    pub fn mel_to_hz(self, m: f32) -> f32 {
+        match self {
+            MelFormula::Htk => 700.0 * (10_f32.powf(m / 2595.0) - 1.0),
+            MelFormula::Slaney => {
+                if m < SLANEY_MIN_LOG_MEL {
+                    m * SLANEY_HZ_PER_MEL
+                } else {
+                    SLANEY_MIN_LOG_HZ * (slaney_log_step() * (m - SLANEY_MIN_LOG_MEL)).exp()
+                }
+            },
+        }
+    }
+}
+
+/// The step of the linear part of the Slaney formula
+const SLANEY_HZ_PER_MEL: f32 = 200.0 / 3.0;
+/// The frequency where the Slaney formula becomes logarithmic
+const SLANEY_MIN_LOG_HZ: f32 = 1000.0;
+const SLANEY_MIN_LOG_MEL: f32 = SLANEY_MIN_LOG_HZ / SLANEY_HZ_PER_MEL;
+
+/// The logarithmic step of the Slaney formula, 27 Mels per factor of 6.4
+fn slaney_log_step() -> f32 {
+    6.4f32.ln() / 27.0
+}
+
+/// Convert frequency (Hz) to Mel scale using the HTK formula
 pub fn hz_to_mel(f: f32) -> f32 {
-    2595.0 * (1.0 + f / 700.0).log10()
+    MelFormula::Htk.hz_to_mel(f)
 }
 
-/// Convert Mel scale value to frequency (Hz)
+/// Convert Mel scale value to frequency (Hz) using the HTK formula
 pub fn mel_to_hz(m: f32) -> f32 {
-    700.0 * (10_f32.powf(m / 2595.0) - 1.0)
+    MelFormula::Htk.mel_to_hz(m)
 }
 
 /// Generate linearly spaced values between start and stop with num total points
@@ -40,6 +104,21 @@ mod tests {
         assert!(hz > 0.0);
     }
 
+    #[test]
+    fn test_slaney_formula() {
+        let formula = MelFormula::Slaney;
+
+        // Linear below 1 kHz
+        assert!((formula.hz_to_mel(500.0) - 7.5).abs() < 1e-4);
+        assert!((formula.hz_to_mel(1000.0) - 15.0).abs() < 1e-4);
+        // Reference value of librosa
+        assert!((formula.hz_to_mel(4000.0) - 35.163_97).abs() < 1e-3);
+
+        for &f in &[100.0, 1000.0, 4000.0, 16000.0] {
+            assert!((formula.mel_to_hz(formula.hz_to_mel(f)) - f).abs() < f * 1e-4);
+        }
+    }
+
     #[test]
     fn test_linspace() {
         let values = linspace(0.0, 10.0, 11);
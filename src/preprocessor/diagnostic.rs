@@ -0,0 +1,53 @@
+use std::fmt;
+use super::SourceMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found while preprocessing the shader source.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The 1-based line of the preprocessed source
+    pub line: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Formats the diagnostic, pointing at the original file and line.
+    pub fn format(&self, source_map: &SourceMap) -> String {
+        if let Some((file, line)) = source_map.location(self.line) {
+            format!("{} ({}): {}: {}", file.display(), line, self.severity, self.message)
+        } else {
+            format!("line {}: {}: {}", self.line, self.severity, self.message)
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use regex::Regex;
+use super::SourceMap;
+
+/// A shader source with all include directives recursively replaced by the
+/// contents of the included files.
+pub struct ExpandedSource {
+    pub source: String,
+    pub source_map: SourceMap,
+    /// All files the source was assembled from, starting with the root file.
+    pub files: Vec<PathBuf>,
+}
+
+/// An error expanding the includes of a shader.
+#[derive(Debug)]
+pub struct IncludeError {
+    pub message: Cow<'static, str>,
+    /// The files resolved before the error occurred, including the files that could not be found,
+    /// so that they can be watched for the shader to be reloaded once it is fixed.
+    pub files: Vec<PathBuf>,
+}
+
+struct IncludeExpander<'a> {
+    pattern: Regex,
+    include_directories: &'a [PathBuf],
+    /// Files currently being expanded, used to detect include cycles.
+    stack: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    output: String,
+    source_map: SourceMap,
+}
+
+impl<'a> IncludeExpander<'a> {
+    /// The paths an include is looked up at, in order.
+    fn candidates(&self, include_path: &str, angled: bool, including_file: &Path) -> Vec<PathBuf> {
+        let include_path = Path::new(include_path);
+
+        if include_path.is_absolute() {
+            return vec![include_path.to_path_buf()];
+        }
+
+        // Quoted includes are looked up relative to the including file first,
+        // angled includes only in the include directories, like in C.
+        let relative_directory = if angled {
+            None
+        } else {
+            including_file.parent()
+        };
+
+        relative_directory.into_iter()
+            .chain(self.include_directories.iter().map(|directory| directory.as_path()))
+            .map(|directory| directory.join(include_path))
+            .collect()
+    }
+
+    fn expand_file(&mut self, path: &Path) -> Result<(), Cow<'static, str>> {
+        let canonical_path = match path.canonicalize() {
+            Ok(canonical_path) => canonical_path,
+            Err(err) => {
+                self.files.push(path.to_path_buf());
+                throw!(format!("Could not access the shader file `{}`: {}", path.display(), err));
+            },
+        };
+
+        if let Some(cycle_start) = self.stack.iter().position(|file| file == &canonical_path) {
+            let cycle = self.stack[cycle_start..].iter()
+                .chain(std::iter::once(&canonical_path))
+                .map(|file| format!("`{}`", file.display()))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            throw!(format!("Include cycle detected: {}", cycle));
+        }
+
+        // Every file is included at most once, as if it contained `#pragma once`.
+        if self.files.contains(&canonical_path) {
+            return Ok(());
+        }
+
+        let source = fs::read_to_string(&canonical_path)
+            .map_err(|err| format!("Could not read the shader file `{}`: {}", path.display(), err))?;
+
+        self.stack.push(canonical_path.clone());
+        self.files.push(canonical_path.clone());
+
+        for (line_index, line) in source.lines().enumerate() {
+            let captures = if let Some(captures) = self.pattern.captures(line) {
+                captures
+            } else {
+                self.output.push_str(line);
+                self.output.push('\n');
+                self.source_map.push_line(&canonical_path, line_index + 1);
+                continue;
+            };
+
+            let (include_path, angled) = if let Some(quoted) = captures.name("quoted") {
+                (quoted.as_str(), false)
+            } else {
+                (captures.name("angled").unwrap().as_str(), true)
+            };
+            let candidates = self.candidates(include_path, angled, &canonical_path);
+            let resolved_path = match candidates.iter().find(|candidate| candidate.is_file()) {
+                Some(resolved_path) => resolved_path.clone(),
+                None => {
+                    let message = format!(
+                        "Could not find the file `{}` included from `{}` on line {}. Searched in: {}",
+                        include_path,
+                        path.display(),
+                        line_index + 1,
+                        canonical_path.parent().filter(|_| !angled).into_iter()
+                            .chain(self.include_directories.iter().map(|directory| directory.as_path()))
+                            .map(|directory| format!("`{}`", directory.display()))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    );
+
+                    // Watch every location the file is looked up at, so that it is found once created
+                    self.files.extend(candidates);
+                    throw!(message);
+                },
+            };
+
+            self.expand_file(&resolved_path)?;
+        }
+
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+/// Reads the shader at `path` and recursively expands the following directives:
+/// `#pragma shaderfilter include "<path>"`
+/// `#include "<path>"`
+/// `#include <path>`
+pub fn expand_includes(path: &Path, include_directories: &[PathBuf]) -> Result<ExpandedSource, IncludeError> {
+    let mut expander = IncludeExpander {
+        pattern: Regex::new(r#"^[ \t]*#[ \t]*(?:pragma[ \t]+shaderfilter[ \t]+include|include)[ \t]+(?:"(?P<quoted>[^"]+)"|<(?P<angled>[^>]+)>)[ \t]*$"#).unwrap(),
+        include_directories,
+        stack: Vec::new(),
+        files: Vec::new(),
+        output: String::new(),
+        source_map: SourceMap::default(),
+    };
+
+    if let Err(message) = expander.expand_file(path) {
+        return Err(IncludeError {
+            message,
+            files: expander.files,
+        });
+    }
+
+    Ok(ExpandedSource {
+        source: expander.output,
+        source_map: expander.source_map,
+        files: expander.files,
+    })
+}
+
+/// Parses a list of include directories separated by `;`.
+pub fn parse_include_directories(directories: &str) -> Vec<PathBuf> {
+    directories.split(';')
+        .map(|directory| directory.trim())
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary directory, which is removed along with its contents when dropped.
+    struct TestDirectory(PathBuf);
+
+    impl std::ops::Deref for TestDirectory {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn test_directory(name: &str) -> TestDirectory {
+        let directory = std::env::temp_dir()
+            .join(format!("obs-shaderfilter-plus-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        TestDirectory(directory)
+    }
+
+    #[test]
+    fn test_nested_includes() {
+        let directory = test_directory("nested-includes");
+        fs::create_dir_all(directory.join("lib")).unwrap();
+        fs::write(directory.join("main.hlsl"), "#include \"lib/color.hlsl\"\nmain\n").unwrap();
+        fs::write(directory.join("lib/color.hlsl"), "#pragma shaderfilter include \"common.hlsl\"\ncolor\n").unwrap();
+        fs::write(directory.join("lib/common.hlsl"), "common\n").unwrap();
+
+        let expanded = expand_includes(&directory.join("main.hlsl"), &[]).unwrap();
+
+        assert_eq!(expanded.source, "common\ncolor\nmain\n");
+        assert_eq!(expanded.files.len(), 3);
+        assert_eq!(expanded.source_map.location(2), Some((directory.join("lib/color.hlsl").canonicalize().unwrap().as_path(), 2)));
+        assert_eq!(expanded.source_map.location(3), Some((directory.join("main.hlsl").canonicalize().unwrap().as_path(), 2)));
+    }
+
+    #[test]
+    fn test_include_once() {
+        let directory = test_directory("include-once");
+        fs::write(directory.join("main.hlsl"), "#include \"a.hlsl\"\n#include \"b.hlsl\"\n").unwrap();
+        fs::write(directory.join("a.hlsl"), "#include \"common.hlsl\"\na\n").unwrap();
+        fs::write(directory.join("b.hlsl"), "#include \"common.hlsl\"\nb\n").unwrap();
+        fs::write(directory.join("common.hlsl"), "common\n").unwrap();
+
+        let expanded = expand_includes(&directory.join("main.hlsl"), &[]).unwrap();
+
+        assert_eq!(expanded.source, "common\na\nb\n");
+    }
+
+    #[test]
+    fn test_include_directories() {
+        let directory = test_directory("include-directories");
+        fs::create_dir_all(directory.join("shaders")).unwrap();
+        fs::create_dir_all(directory.join("library")).unwrap();
+        fs::write(directory.join("shaders/main.hlsl"), "#include <noise.hlsl>\n").unwrap();
+        fs::write(directory.join("library/noise.hlsl"), "noise\n").unwrap();
+
+        let include_directories = parse_include_directories(&format!(" ; {};", directory.join("library").display()));
+        let expanded = expand_includes(&directory.join("shaders/main.hlsl"), &include_directories).unwrap();
+
+        assert_eq!(expanded.source, "noise\n");
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let directory = test_directory("include-cycle");
+        fs::write(directory.join("a.hlsl"), "#include \"b.hlsl\"\n").unwrap();
+        fs::write(directory.join("b.hlsl"), "#include \"a.hlsl\"\n").unwrap();
+
+        let error = expand_includes(&directory.join("a.hlsl"), &[]).err().unwrap();
+
+        assert!(error.message.contains("Include cycle detected"));
+    }
+
+    #[test]
+    fn test_missing_include() {
+        let directory = test_directory("missing-include");
+        fs::write(directory.join("main.hlsl"), "\n#include \"missing.hlsl\"\n").unwrap();
+
+        let error = expand_includes(&directory.join("main.hlsl"), &[]).err().unwrap();
+
+        assert!(error.message.contains("`missing.hlsl`"));
+        assert!(error.message.contains("on line 2"));
+    }
+
+    #[test]
+    fn test_missing_include_files() {
+        let directory = test_directory("missing-include-files");
+        fs::create_dir_all(directory.join("library")).unwrap();
+        fs::write(directory.join("main.hlsl"), "#include \"common.hlsl\"\n#include \"missing.hlsl\"\n").unwrap();
+        fs::write(directory.join("common.hlsl"), "common\n").unwrap();
+
+        let canonical_directory = directory.canonicalize().unwrap();
+        let include_directories = [directory.join("library")];
+        let error = expand_includes(&directory.join("main.hlsl"), &include_directories).err().unwrap();
+
+        assert_eq!(error.files, vec![
+            canonical_directory.join("main.hlsl"),
+            canonical_directory.join("common.hlsl"),
+            canonical_directory.join("missing.hlsl"),
+            directory.join("library/missing.hlsl"),
+        ]);
+
+        let error = expand_includes(&directory.join("removed.hlsl"), &[]).err().unwrap();
+
+        assert_eq!(error.files, vec![directory.join("removed.hlsl")]);
+    }
+}
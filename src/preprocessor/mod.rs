@@ -0,0 +1,431 @@
+use std::str::FromStr;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use regex::Regex;
+use anyhow::Result;
+
+mod diagnostic;
+mod include;
+mod source_map;
+mod pass;
+pub mod shadertoy;
+
+pub use shadertoy::ShaderMode;
+pub use diagnostic::*;
+pub use include::*;
+pub use source_map::*;
+pub use pass::*;
+
+/// A value assigned to a property using `#pragma shaderfilter set`.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub value: String,
+    /// The 1-based line of the preprocessed source
+    pub line: usize,
+}
+
+#[derive(Default)]
+pub struct PreprocessResult {
+    map: HashMap<String, Definition>,
+    diagnostics: Vec<Diagnostic>,
+    /// Identifiers looked up while binding effect params, used to find unused definitions.
+    queried: RefCell<HashSet<String>>,
+    /// The mode specified using `#pragma shaderfilter mode`
+    mode: Option<ShaderMode>,
+    /// The intermediate passes declared using `#pragma shaderfilter pass`, in the order they are rendered
+    passes: Vec<RenderPass>,
+}
+
+impl PreprocessResult {
+    pub fn parse<T: FromStr>(&self, identifier: &str) -> Option<Result<T, Cow<'static, str>>> {
+        self.queried.borrow_mut().insert(identifier.to_string());
+        self.map.get(identifier)
+            .map(|definition| {
+                definition.value.parse::<T>().map_err(|_| {
+                    Cow::Owned(format!(
+                        "Could not parse property `{}` of type `{}`.",
+                        identifier,
+                        std::any::type_name::<T>(),
+                    ))
+                })
+            })
+    }
+
+    pub fn parse_default<T: FromStr>(&self, identifier: &str, default: Option<T>) -> Result<T, Cow<'static, str>> {
+        self.parse::<T>(identifier)
+            .or_else(|| {
+                default.map(|default| Ok(default))
+            })
+            .ok_or_else(|| {
+                Cow::Owned(format!(
+                    "Property `{}` is missing a definition.",
+                    identifier,
+                ))
+            })
+            .and_then(|result| result)
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn mode(&self) -> Option<ShaderMode> {
+        self.mode
+    }
+
+    pub fn passes(&self) -> &[RenderPass] {
+        &self.passes
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Returns a warning for every definition that was never looked up, suggesting
+    /// the closest identifier that was looked up, if there is a similar one.
+    /// Must be called after all effect params are bound.
+    pub fn unused_definition_diagnostics(&self) -> Vec<Diagnostic> {
+        let queried = self.queried.borrow();
+        let mut unused = self.map.iter()
+            .filter(|(identifier, _)| !queried.contains(*identifier))
+            .collect::<Vec<_>>();
+
+        unused.sort_by_key(|(_, definition)| definition.line);
+        unused.into_iter()
+            .map(|(identifier, definition)| {
+                let max_distance = std::cmp::max(2, identifier.len() / 4);
+                let suggestion = queried.iter()
+                    .map(|candidate| (levenshtein_distance(identifier, candidate), candidate))
+                    .filter(|(distance, _)| *distance <= max_distance)
+                    .min();
+
+                if let Some((_, suggestion)) = suggestion {
+                    Diagnostic::warning(definition.line, format!(
+                        "Property `{}` is never used. Did you mean `{}`?",
+                        identifier,
+                        suggestion,
+                    ))
+                } else {
+                    Diagnostic::warning(definition.line, format!(
+                        "Property `{}` is never used.",
+                        identifier,
+                    ))
+                }
+            })
+            .collect()
+    }
+
+    fn process_pragma(&mut self, line: usize, arguments: &str) {
+        let (verb, arguments) = split_first_word(arguments);
+
+        match verb {
+            "" => {
+                self.diagnostics.push(Diagnostic::error(line, "Missing verb, expected `#pragma shaderfilter set <identifier> <value>`."));
+            },
+            "set" => {
+                let (identifier, value) = split_first_word(arguments);
+
+                if identifier.is_empty() {
+                    self.diagnostics.push(Diagnostic::error(line, "Missing property identifier, expected `#pragma shaderfilter set <identifier> <value>`."));
+                    return;
+                }
+
+                if !identifier.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    self.diagnostics.push(Diagnostic::error(line, format!("Invalid property identifier `{}`.", identifier)));
+                    return;
+                }
+
+                if value.is_empty() {
+                    self.diagnostics.push(Diagnostic::error(line, format!("Missing value of property `{}`.", identifier)));
+                    return;
+                }
+
+                let previous = self.map.insert(identifier.to_string(), Definition {
+                    value: value.to_string(),
+                    line,
+                });
+
+                if let Some(previous) = previous {
+                    self.diagnostics.push(Diagnostic::warning(line, format!(
+                        "Property `{}` was already defined on line {}, the previous definition is overridden.",
+                        identifier,
+                        previous.line,
+                    )));
+                }
+            },
+            "mode" => {
+                match ShaderMode::parse(arguments) {
+                    Some(mode) => self.mode = Some(mode),
+                    None => {
+                        self.diagnostics.push(Diagnostic::error(line, format!("Unknown mode `{}`, expected one of: `default`, `shadertoy`.", arguments)));
+                    },
+                }
+            },
+            "pass" => {
+                if let Some(pass) = self.parse_pass(line, arguments) {
+                    self.passes.push(pass);
+                }
+            },
+            "include" => {
+                // Well-formed includes are expanded before preprocessing.
+                self.diagnostics.push(Diagnostic::error(line, "Malformed include directive, expected `#pragma shaderfilter include \"<path>\"`."));
+            },
+            verb => {
+                self.diagnostics.push(Diagnostic::error(line, format!("Unknown verb `{}`, expected one of: `set`, `include`, `mode`, `pass`.", verb)));
+            },
+        }
+    }
+
+    /// Parses `#pragma shaderfilter pass <name> <function> [<scale> [<format>]]`, reporting errors as diagnostics.
+    fn parse_pass(&mut self, line: usize, arguments: &str) -> Option<RenderPass> {
+        const SYNTAX: &str = "expected `#pragma shaderfilter pass <name> <function> [<scale> [<format>]]`";
+
+        let words = arguments.split_whitespace().collect::<Vec<_>>();
+
+        if words.len() < 2 || words.len() > 4 {
+            self.diagnostics.push(Diagnostic::error(line, format!("Malformed pass declaration, {}.", SYNTAX)));
+            return None;
+        }
+
+        let (name, function) = (words[0], words[1]);
+
+        for identifier in &[name, function] {
+            if !is_identifier(identifier) {
+                self.diagnostics.push(Diagnostic::error(line, format!("Invalid identifier `{}`, {}.", identifier, SYNTAX)));
+                return None;
+            }
+        }
+
+        if name == "image" || name.starts_with("builtin_") {
+            self.diagnostics.push(Diagnostic::error(line, format!("The pass name `{}` is reserved.", name)));
+            return None;
+        }
+
+        if let Some(previous) = self.passes.iter().find(|pass| pass.name == name) {
+            self.diagnostics.push(Diagnostic::error(line, format!(
+                "Pass `{}` was already declared on line {}.",
+                name,
+                previous.line,
+            )));
+            return None;
+        }
+
+        let scale = match words.get(2).map(|scale| scale.parse::<f32>()) {
+            None => 1.0,
+            Some(Ok(scale)) if scale.is_finite() && scale > 0.0 => scale,
+            Some(_) => {
+                self.diagnostics.push(Diagnostic::error(line, format!("Invalid scale `{}` of pass `{}`, expected a positive number.", words[2], name)));
+                return None;
+            },
+        };
+
+        let format = match words.get(3) {
+            None => PassFormat::default(),
+            Some(format) => match PassFormat::from_name(format) {
+                Some(format) => format,
+                None => {
+                    self.diagnostics.push(Diagnostic::error(line, format!(
+                        "Unknown format `{}` of pass `{}`, expected one of: {}.",
+                        format,
+                        name,
+                        PassFormat::NAMES.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", "),
+                    )));
+                    return None;
+                },
+            },
+        };
+
+        Some(RenderPass {
+            name: name.to_string(),
+            function: function.to_string(),
+            scale,
+            format,
+            line,
+        })
+    }
+}
+
+/// The number of single-character insertions, deletions and substitutions
+/// needed to turn `a` into `b`.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+
+        row[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + if a_char == *b_char { 0 } else { 1 };
+
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Splits off the first whitespace-delimited word, returning it and the trimmed remainder.
+fn split_first_word(string: &str) -> (&str, &str) {
+    let string = string.trim();
+
+    match string.find(char::is_whitespace) {
+        Some(index) => (&string[..index], string[index..].trim_start()),
+        None => (string, ""),
+    }
+}
+
+/// Processes and strips all `#pragma shaderfilter` lines.
+/// Stripped lines are left empty, so that line numbers stay the same.
+pub fn preprocess(source: &str) -> (PreprocessResult, Cow<str>) {
+    let mut result = PreprocessResult::default();
+    // Matches on macros:
+    // #pragma shaderfilter <verb> <arguments>
+    let pattern = Regex::new(r"^[ \t]*#[ \t]*pragma[ \t]+shaderfilter(?:[ \t]+(?P<arguments>.*?))?[ \t]*\r?$").unwrap();
+
+    if !source.lines().any(|line| pattern.is_match(line)) {
+        return (result, Cow::Borrowed(source));
+    }
+
+    let lines = source.split('\n')
+        .enumerate()
+        .map(|(line_index, line)| {
+            if let Some(captures) = pattern.captures(line) {
+                let arguments = captures.name("arguments").map(|arguments| arguments.as_str()).unwrap_or("");
+
+                result.process_pragma(line_index + 1, arguments);
+                ""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (result, Cow::Owned(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preprocess_set() {
+        let (result, source) = preprocess("#pragma shaderfilter set color__default 7FFF00FF\nuniform float4 color;\n");
+
+        assert_eq!(source, "\nuniform float4 color;\n");
+        assert_eq!(result.parse::<String>("color__default").unwrap().unwrap(), "7FFF00FF");
+        assert!(result.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_preprocess_diagnostics() {
+        let (result, source) = preprocess(concat!(
+            "#pragma shaderfilter set value__default 1\n",
+            "  #pragma shaderfilter set value__default 2\n",
+            "#pragma shaderfilter sett value__min 0\n",
+            "#pragma shaderfilter set value__max\n",
+            "#pragma shaderfilter\n",
+            "uniform float value;",
+        ));
+
+        assert_eq!(source, "\n\n\n\n\nuniform float value;");
+        assert_eq!(result.parse::<i32>("value__default").unwrap().unwrap(), 2);
+        assert!(result.parse::<f32>("value__min").is_none());
+        assert!(result.has_errors());
+
+        let diagnostics = result.diagnostics().iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(diagnostics, vec![
+            (Severity::Warning, 2),
+            (Severity::Error, 3),
+            (Severity::Error, 4),
+            (Severity::Error, 5),
+        ]);
+    }
+
+    #[test]
+    fn test_preprocess_mode() {
+        let (result, _) = preprocess("#pragma shaderfilter mode shadertoy\n");
+
+        assert_eq!(result.mode(), Some(ShaderMode::Shadertoy));
+
+        let (result, _) = preprocess("#pragma shaderfilter mode glsl\n");
+
+        assert_eq!(result.mode(), None);
+        assert!(result.has_errors());
+    }
+
+    #[test]
+    fn test_preprocess_pass() {
+        let (result, source) = preprocess(concat!(
+            "#pragma shaderfilter pass horizontal blur_horizontal 0.5\n",
+            "#pragma shaderfilter pass vertical blur_vertical 0.5 rgba16f\n",
+            "#pragma shaderfilter pass bloom\n",
+            "#pragma shaderfilter pass horizontal blur_horizontal\n",
+            "#pragma shaderfilter pass image copy\n",
+            "#pragma shaderfilter pass small downsample 0\n",
+            "#pragma shaderfilter pass wide upsample 2 rgb\n",
+        ));
+
+        assert_eq!(source, "\n\n\n\n\n\n\n");
+        assert_eq!(result.passes(), &[
+            RenderPass {
+                name: "horizontal".to_string(),
+                function: "blur_horizontal".to_string(),
+                scale: 0.5,
+                format: PassFormat::Rgba,
+                line: 1,
+            },
+            RenderPass {
+                name: "vertical".to_string(),
+                function: "blur_vertical".to_string(),
+                scale: 0.5,
+                format: PassFormat::Rgba16F,
+                line: 2,
+            },
+        ]);
+
+        let errors = result.diagnostics().iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors, (3..=7).map(|line| (Severity::Error, line)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("my_colr", "my_color"), 1);
+        assert_eq!(levenshtein_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_unused_definitions() {
+        let (result, _) = preprocess(concat!(
+            "#pragma shaderfilter set my_colr__default 7FFF00FF\n",
+            "#pragma shaderfilter set my_color__descripton My Color\n",
+            "#pragma shaderfilter set my_color__min 0\n",
+            "#pragma shaderfilter set unrelated 1\n",
+        ));
+
+        for identifier in &["my_color", "my_color__default", "my_color__description", "my_color__min"] {
+            let _ = result.parse::<String>(identifier);
+        }
+
+        let messages = result.unused_definition_diagnostics().into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message))
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec![
+            (1, "Property `my_colr__default` is never used. Did you mean `my_color__default`?".to_string()),
+            (2, "Property `my_color__descripton` is never used. Did you mean `my_color__description`?".to_string()),
+            (4, "Property `unrelated` is never used.".to_string()),
+        ]);
+    }
+}
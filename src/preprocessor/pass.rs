@@ -0,0 +1,152 @@
+/// The color format of the texture an intermediate pass is rendered into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PassFormat {
+    #[default]
+    Rgba,
+    Rgba16F,
+    Rgba32F,
+    R16F,
+    R32F,
+}
+
+impl PassFormat {
+    /// Names of the formats as used in `#pragma shaderfilter pass`.
+    pub const NAMES: &'static [&'static str] = &["rgba", "rgba16f", "rgba32f", "r16f", "r32f"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        use PassFormat::*;
+
+        Some(match name {
+            "rgba" => Rgba,
+            "rgba16f" => Rgba16F,
+            "rgba32f" => Rgba32F,
+            "r16f" => R16F,
+            "r32f" => R32F,
+            _ => return None,
+        })
+    }
+}
+
+/// An intermediate pass declared using `#pragma shaderfilter pass`, rendered into the texture `name`
+/// before the final `render` pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderPass {
+    pub name: String,
+    /// The function of the signature `float4 <function>(float2 uv)` rendering the pass
+    pub function: String,
+    /// The dimensions of the texture relative to the dimensions of the filtered source
+    pub scale: f32,
+    pub format: PassFormat,
+    /// The 1-based line of the preprocessed source
+    pub line: usize,
+}
+
+impl RenderPass {
+    /// The dimensions of the texture of the pass, given the dimensions of the filtered source.
+    pub fn dimensions(&self, source_dimensions: [u32; 2]) -> [u32; 2] {
+        let scale = |dimension: u32| ((dimension as f32 * self.scale).round() as u32).max(1);
+
+        [scale(source_dimensions[0]), scale(source_dimensions[1])]
+    }
+}
+
+/// Whether the string is a valid HLSL identifier.
+pub fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+
+    chars.next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replaces the `__PASS_DECLARATIONS__` and `__PASS_DISPATCH__` lines of the effect template.
+/// The pass textures are declared along with `builtin_pass`, the index of the pass being rendered,
+/// which selects the function of the pass in the fragment shader. The final pass has the index
+/// equal to the number of passes. Each replacement is placed on a single line, so that the template
+/// line numbers stay the same. Without any passes, both lines are left empty.
+pub fn prepare_pass_template(template: &str, passes: &[RenderPass]) -> String {
+    let declarations = if passes.is_empty() {
+        String::new()
+    } else {
+        std::iter::once("uniform int builtin_pass;".to_string())
+            .chain(passes.iter().map(|pass| format!("uniform texture2d {};", pass.name)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let dispatch = passes.iter()
+        .enumerate()
+        .map(|(index, pass)| format!("if (builtin_pass == {}) return {}(v_in.uv);", index, pass.function))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    template.lines()
+        .map(|line| {
+            match line.trim() {
+                "__PASS_DECLARATIONS__" => declarations.as_str(),
+                "__PASS_DISPATCH__" => dispatch.as_str(),
+                _ => line,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimensions() {
+        let pass = RenderPass {
+            name: "blur".to_string(),
+            function: "blur_horizontal".to_string(),
+            scale: 0.5,
+            format: PassFormat::Rgba,
+            line: 1,
+        };
+
+        assert_eq!(pass.dimensions([1920, 1080]), [960, 540]);
+        assert_eq!(pass.dimensions([1, 3]), [1, 2]);
+    }
+
+    #[test]
+    fn test_prepare_pass_template() {
+        let template = "uniform texture2d image;\n__PASS_DECLARATIONS__\n__SHADER__\n    __PASS_DISPATCH__\n    return render(v_in.uv);";
+        let passes = vec![
+            RenderPass {
+                name: "horizontal".to_string(),
+                function: "blur_horizontal".to_string(),
+                scale: 1.0,
+                format: PassFormat::Rgba,
+                line: 1,
+            },
+            RenderPass {
+                name: "vertical".to_string(),
+                function: "blur_vertical".to_string(),
+                scale: 1.0,
+                format: PassFormat::Rgba16F,
+                line: 2,
+            },
+        ];
+
+        assert_eq!(prepare_pass_template(template, &passes), concat!(
+            "uniform texture2d image;\n",
+            "uniform int builtin_pass; uniform texture2d horizontal; uniform texture2d vertical;\n",
+            "__SHADER__\n",
+            "if (builtin_pass == 0) return blur_horizontal(v_in.uv); if (builtin_pass == 1) return blur_vertical(v_in.uv);\n",
+            "    return render(v_in.uv);",
+        ));
+        assert_eq!(
+            prepare_pass_template(template, &[]),
+            "uniform texture2d image;\n\n__SHADER__\n\n    return render(v_in.uv);",
+        );
+    }
+
+    #[test]
+    fn test_is_identifier() {
+        assert!(is_identifier("blur_1"));
+        assert!(is_identifier("_blur"));
+        assert!(!is_identifier("1blur"));
+        assert!(!is_identifier("blur-1"));
+        assert!(!is_identifier(""));
+    }
+}
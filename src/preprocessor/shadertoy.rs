@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+use regex::{Regex, Captures};
+
+/// Determines which effect template the shader is inserted into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderMode {
+    /// The shader defines `float4 render(float2 uv)`.
+    Default,
+    /// The shader defines `void mainImage(out vec4 fragColor, in vec2 fragCoord)`, as on Shadertoy.
+    Shadertoy,
+}
+
+impl ShaderMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "default" => Some(ShaderMode::Default),
+            "shadertoy" => Some(ShaderMode::Shadertoy),
+            _ => None,
+        }
+    }
+
+    /// Shaders defining `mainImage`, but not `render`, are assumed to come from Shadertoy.
+    pub fn detect(source: &str) -> Self {
+        let pattern_main_image = Regex::new(r"\bmainImage\s*\(").unwrap();
+        let pattern_render = Regex::new(r"\brender\s*\(").unwrap();
+
+        if pattern_main_image.is_match(source) && !pattern_render.is_match(source) {
+            ShaderMode::Shadertoy
+        } else {
+            ShaderMode::Default
+        }
+    }
+}
+
+/// Shadertoy inputs and the expressions they are replaced with.
+const SHADERTOY_INPUTS: &[(&str, &str)] = &[
+    ("iTime", "builtin_elapsed_time"),
+    ("iGlobalTime", "builtin_elapsed_time"),
+    ("iTimeDelta", "(builtin_elapsed_time - builtin_elapsed_time_previous)"),
+    ("iFrame", "builtin_frame"),
+    ("iFrameRate", "builtin_framerate"),
+    ("iResolution", "float3(float2(builtin_uv_size), 1.0)"),
+    ("iMouse", "float4(0.0, 0.0, 0.0, 0.0)"),
+    ("iDate", "builtin_date"),
+    ("iChannel0", "image"),
+];
+
+/// GLSL identifiers and their HLSL equivalents.
+const GLSL_IDENTIFIERS: &[(&str, &str)] = &[
+    ("vec2", "float2"),
+    ("vec3", "float3"),
+    ("vec4", "float4"),
+    ("ivec2", "int2"),
+    ("ivec3", "int3"),
+    ("ivec4", "int4"),
+    ("uvec2", "uint2"),
+    ("uvec3", "uint3"),
+    ("uvec4", "uint4"),
+    ("bvec2", "bool2"),
+    ("bvec3", "bool3"),
+    ("bvec4", "bool4"),
+    ("mat2", "float2x2"),
+    ("mat3", "float3x3"),
+    ("mat4", "float4x4"),
+    ("mix", "lerp"),
+    ("fract", "frac"),
+    ("mod", "builtin_mod"),
+    ("inversesqrt", "rsqrt"),
+    ("dFdx", "ddx"),
+    ("dFdy", "ddy"),
+];
+
+/// Texture channels which are declared as custom `texture2d` uniforms, if they are used.
+const SHADERTOY_CHANNELS: &[&str] = &["iChannel1", "iChannel2", "iChannel3"];
+
+/// Matches comments and string literals, which are left untranslated.
+const PATTERN_COMMENT_OR_STRING: &str = r#"//[^\n]*|/\*(?s:.*?)\*/|"(?:[^"\\\n]|\\.)*""#;
+
+/// Returns the end of the comment or string literal starting at `index`, if there is one.
+fn skip_comment_or_string(source: &str, index: usize) -> Option<usize> {
+    let rest = &source[index..];
+
+    if rest.starts_with("//") {
+        Some(rest.find('\n').map(|end| index + end).unwrap_or(source.len()))
+    } else if let Some(comment) = rest.strip_prefix("/*") {
+        Some(comment.find("*/").map(|end| index + 2 + end + 2).unwrap_or(source.len()))
+    } else if rest.starts_with('"') {
+        let mut escaped = false;
+
+        for (offset, character) in rest.char_indices().skip(1) {
+            match character {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' | '\n' => return Some(index + offset + 1),
+                _ => (),
+            }
+        }
+
+        Some(source.len())
+    } else {
+        None
+    }
+}
+
+/// Finds the index of the parenthesis closing the call whose arguments start at `start`,
+/// along with the byte ranges of the top-level arguments.
+fn parse_call_arguments(source: &str, start: usize) -> Option<(usize, Vec<(usize, usize)>)> {
+    let mut depth = 0;
+    let mut arguments = Vec::new();
+    let mut argument_start = start;
+    let mut index = start;
+
+    while let Some(character) = source[index..].chars().next() {
+        if let Some(end) = skip_comment_or_string(source, index) {
+            index = end;
+            continue;
+        }
+
+        match character {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth > 0 => depth -= 1,
+            ')' => {
+                arguments.push((argument_start, index));
+                return Some((index, arguments));
+            },
+            ',' if depth == 0 => {
+                arguments.push((argument_start, index));
+                argument_start = index + 1;
+            },
+            _ => (),
+        }
+
+        index += character.len_utf8();
+    }
+
+    None
+}
+
+/// Rewrites calls of functions, which cannot be translated by renaming them.
+/// Newlines within the calls are kept, so that line numbers stay the same.
+fn translate_calls(source: &str) -> String {
+    let pattern = Regex::new(&format!(
+        r"(?P<skipped>{})|\b(?P<function>texture|texture2D|textureLod|atan|float[234]|int[234]|uint[234]|bool[234])\s*\(",
+        PATTERN_COMMENT_OR_STRING,
+    )).unwrap();
+    let mut result = String::with_capacity(source.len());
+    let mut position = 0;
+
+    while let Some(captures) = pattern.captures(&source[position..]) {
+        let call = captures.get(0).unwrap();
+
+        if captures.name("skipped").is_some() {
+            result.push_str(&source[position..position + call.end()]);
+            position += call.end();
+            continue;
+        }
+
+        let call_start = position + call.start();
+        let arguments_start = position + call.end();
+        let function = captures.name("function").unwrap().as_str();
+
+        result.push_str(&source[position..call_start]);
+
+        let (call_end, arguments) = if let Some(parsed) = parse_call_arguments(source, arguments_start) {
+            parsed
+        } else {
+            // Unbalanced parentheses, leave the rest of the source as is
+            position = call_start;
+            break;
+        };
+        let arguments = arguments.iter()
+            .map(|&(start, end)| translate_calls(&source[start..end]))
+            .collect::<Vec<_>>();
+        let is_constructor = !matches!(function, "texture" | "texture2D" | "textureLod" | "atan");
+
+        match (function, arguments.len()) {
+            ("texture", 2..=3) | ("texture2D", 2..=3) => {
+                // Shadertoy textures are sampled with the origin in the bottom left corner
+                result.push_str(&format!(
+                    "{}.Sample(builtin_texture_sampler, builtin_shadertoy_flip({}))",
+                    arguments[0].trim(),
+                    arguments[1].trim(),
+                ));
+            },
+            ("textureLod", 3) => {
+                result.push_str(&format!(
+                    "{}.SampleLevel(builtin_texture_sampler, builtin_shadertoy_flip({}), {})",
+                    arguments[0].trim(),
+                    arguments[1].trim(),
+                    arguments[2].trim(),
+                ));
+            },
+            ("atan", 2) => {
+                result.push_str(&format!("atan2({})", arguments.join(",")));
+            },
+            (_, 1) if is_constructor => {
+                // GLSL vector constructors accept a single scalar, which HLSL only allows as a cast
+                result.push_str(&format!("(({})({}))", function, arguments[0]));
+            },
+            _ => {
+                result.push_str(&format!("{}({})", &source[call_start..arguments_start - 1], arguments.join(",")));
+            },
+        }
+
+        position = call_end + 1;
+    }
+
+    result.push_str(&source[position..]);
+    result
+}
+
+/// Translates a Shadertoy shader written in GLSL to HLSL, to be inserted into the Shadertoy effect template.
+/// Only common syntax differences are translated, the number of lines stays the same.
+/// Comments and string literals are left as they are.
+pub fn translate(source: &str) -> String {
+    let pattern_identifier = Regex::new(&format!(
+        r"(?P<skipped>{})|\b[A-Za-z_][A-Za-z0-9_]*\b",
+        PATTERN_COMMENT_OR_STRING,
+    )).unwrap();
+    let source = pattern_identifier.replace_all(source, |captures: &Captures| {
+        let identifier = &captures[0];
+
+        if captures.name("skipped").is_some() {
+            return Cow::Owned(identifier.to_string());
+        }
+
+        SHADERTOY_INPUTS.iter()
+            .chain(GLSL_IDENTIFIERS.iter())
+            .find(|(from, _)| *from == identifier)
+            .map(|(_, to)| Cow::Borrowed(*to))
+            .unwrap_or_else(|| Cow::Owned(identifier.to_string()))
+    });
+
+    translate_calls(&source)
+}
+
+/// Returns the declarations of the Shadertoy texture channels used by the source, other than `iChannel0`,
+/// which is bound to the filtered source. The declarations are placed on a single line, so that the
+/// template line numbers stay the same.
+pub fn channel_declarations(source: &str) -> String {
+    let source = Regex::new(PATTERN_COMMENT_OR_STRING).unwrap().replace_all(source, "");
+
+    SHADERTOY_CHANNELS.iter()
+        .filter(|channel| Regex::new(&format!(r"\b{}\b", channel)).unwrap().is_match(&source))
+        .map(|channel| format!("uniform texture2d {};", channel))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replaces the `__SHADERTOY_CHANNELS__` line of the Shadertoy effect template.
+pub fn prepare_template(template: &str, source: &str) -> String {
+    let declarations = channel_declarations(source);
+
+    template.lines()
+        .map(|line| {
+            if line.trim() == "__SHADERTOY_CHANNELS__" {
+                declarations.as_str()
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(ShaderMode::detect("void mainImage(out vec4 c, in vec2 p) {}"), ShaderMode::Shadertoy);
+        assert_eq!(ShaderMode::detect("float4 render(float2 uv) { return 0; }"), ShaderMode::Default);
+        assert_eq!(ShaderMode::detect("void mainImage() {}\nfloat4 render(float2 uv) {}"), ShaderMode::Default);
+    }
+
+    #[test]
+    fn test_translate() {
+        let source = concat!(
+            "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n",
+            "    vec2 uv = fragCoord / iResolution.xy;\n",
+            "    vec3 col = mix(vec3(0.0), texture(iChannel0, uv).rgb, fract(iTime));\n",
+            "    float a = atan(uv.y, uv.x) + atan(1.0);\n",
+            "    fragColor = vec4(col, 1.0);\n",
+            "}\n",
+        );
+        let translated = translate(source);
+
+        assert_eq!(translated, concat!(
+            "void mainImage(out float4 fragColor, in float2 fragCoord) {\n",
+            "    float2 uv = fragCoord / float3(((float2)(builtin_uv_size)), 1.0).xy;\n",
+            "    float3 col = lerp(((float3)(0.0)), image.Sample(builtin_texture_sampler, builtin_shadertoy_flip(uv)).rgb, frac(builtin_elapsed_time));\n",
+            "    float a = atan2(uv.y, uv.x) + atan(1.0);\n",
+            "    fragColor = float4(col, 1.0);\n",
+            "}\n",
+        ));
+        assert_eq!(translated.lines().count(), source.lines().count());
+    }
+
+    #[test]
+    fn test_translate_skips_comments_and_strings() {
+        let source = concat!(
+            "// mix(vec3(0.0), texture(iChannel0, uv), iTime)\n",
+            "/* vec2 uv = fragCoord / iResolution.xy;\n",
+            "   fract(iTime) */ vec2 uv = vec2(0.5);\n",
+            "vec4 col = texture(iChannel0, uv /* ) */);\n",
+            "#define NAME \"iTime vec2\"\n",
+        );
+
+        assert_eq!(translate(source), concat!(
+            "// mix(vec3(0.0), texture(iChannel0, uv), iTime)\n",
+            "/* vec2 uv = fragCoord / iResolution.xy;\n",
+            "   fract(iTime) */ float2 uv = ((float2)(0.5));\n",
+            "float4 col = image.Sample(builtin_texture_sampler, builtin_shadertoy_flip(uv /* ) */));\n",
+            "#define NAME \"iTime vec2\"\n",
+        ));
+        assert_eq!(channel_declarations("// texture(iChannel1, uv)\ntexture(iChannel2, uv)"), "uniform texture2d iChannel2;");
+    }
+
+    #[test]
+    fn test_channel_declarations() {
+        let template = "uniform texture2d image;\n__SHADERTOY_CHANNELS__\n__SHADER__";
+        let prepared = prepare_template(template, "texture(iChannel2, uv) + texture(iChannel0, uv)");
+
+        assert_eq!(prepared, "uniform texture2d image;\nuniform texture2d iChannel2;\n__SHADER__");
+    }
+}
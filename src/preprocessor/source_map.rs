@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+use regex::{Regex, Captures};
+
+/// Maps lines of a generated source back to the files and lines they originate from.
+/// Line numbers are 1-based, as reported by the effect parser.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    files: Vec<PathBuf>,
+    /// `(file index, line)` of every line of the generated source
+    lines: Vec<(usize, usize)>,
+}
+
+impl SourceMap {
+    fn file_index(&mut self, file: &Path) -> usize {
+        if let Some(index) = self.files.iter().position(|known| known == file) {
+            index
+        } else {
+            self.files.push(file.to_path_buf());
+            self.files.len() - 1
+        }
+    }
+
+    /// Records that the next line of the generated source is line `line` of `file`.
+    pub fn push_line(&mut self, file: &Path, line: usize) {
+        let file_index = self.file_index(file);
+
+        self.lines.push((file_index, line));
+    }
+
+    /// Appends all lines of another source map.
+    pub fn extend(&mut self, other: &SourceMap) {
+        for &(file_index, line) in &other.lines {
+            self.push_line(&other.files[file_index], line);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns the original file and line of the given line of the generated source.
+    pub fn location(&self, generated_line: usize) -> Option<(&Path, usize)> {
+        generated_line.checked_sub(1)
+            .and_then(|index| self.lines.get(index))
+            .map(|&(file_index, line)| (self.files[file_index].as_path(), line))
+    }
+
+    /// Rewrites locations of the form `<generated_path> (<line>, <column>)`, as reported by
+    /// the effect parser, to point at the original files and lines.
+    pub fn rewrite_message(&self, message: &str, generated_path: &str) -> String {
+        let pattern = Regex::new(&format!(
+            r"{}\s*\((?P<line>\d+),\s*(?P<column>\d+)\)",
+            regex::escape(generated_path),
+        )).unwrap();
+
+        pattern.replace_all(message, |captures: &Captures| {
+            let location = captures["line"].parse::<usize>().ok()
+                .and_then(|line| self.location(line));
+
+            if let Some((file, line)) = location {
+                format!("{} ({}, {})", file.display(), line, &captures["column"])
+            } else {
+                captures[0].to_string()
+            }
+        }).into_owned()
+    }
+}
+
+/// Replaces the `__SHADER__` line of the effect template with the shader source,
+/// keeping track of where each line of the resulting effect source comes from.
+pub fn splice_template(
+    template_path: &Path,
+    template: &str,
+    shader_source: &str,
+    shader_source_map: &SourceMap,
+) -> (String, SourceMap) {
+    let mut effect_source = String::new();
+    let mut source_map = SourceMap::default();
+
+    for (line_index, line) in template.lines().enumerate() {
+        if line.trim() == "__SHADER__" {
+            effect_source.push_str(shader_source);
+            if !shader_source.is_empty() && !shader_source.ends_with('\n') {
+                effect_source.push('\n');
+            }
+            source_map.extend(shader_source_map);
+        } else {
+            effect_source.push_str(line);
+            effect_source.push('\n');
+            source_map.push_line(template_path, line_index + 1);
+        }
+    }
+
+    (effect_source, source_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shader_source_map() -> SourceMap {
+        let mut source_map = SourceMap::default();
+
+        source_map.push_line(Path::new("common.hlsl"), 1);
+        source_map.push_line(Path::new("common.hlsl"), 2);
+        source_map.push_line(Path::new("main.hlsl"), 2);
+        source_map
+    }
+
+    #[test]
+    fn test_splice_template() {
+        let template = "uniform texture2d image;\n__SHADER__\ntechnique Draw {}\n";
+        let (effect_source, source_map) = splice_template(
+            Path::new("effect_template.hlsl"),
+            template,
+            "a\nb\nc\n",
+            &shader_source_map(),
+        );
+
+        assert_eq!(effect_source, "uniform texture2d image;\na\nb\nc\ntechnique Draw {}\n");
+        assert_eq!(source_map.len(), effect_source.lines().count());
+        assert_eq!(source_map.location(1), Some((Path::new("effect_template.hlsl"), 1)));
+        assert_eq!(source_map.location(4), Some((Path::new("main.hlsl"), 2)));
+        assert_eq!(source_map.location(5), Some((Path::new("effect_template.hlsl"), 3)));
+        assert_eq!(source_map.location(6), None);
+    }
+
+    #[test]
+    fn test_rewrite_message() {
+        let (_, source_map) = splice_template(
+            Path::new("effect_template.hlsl"),
+            "__SHADER__\n",
+            "a\nb\nc\n",
+            &shader_source_map(),
+        );
+        let message = source_map.rewrite_message(
+            "/tmp/main.hlsl (3, 7): Unexpected token\n/tmp/main.hlsl (42, 1): Other\n",
+            "/tmp/main.hlsl",
+        );
+
+        assert_eq!(message, "main.hlsl (2, 7): Unexpected token\n/tmp/main.hlsl (42, 1): Other\n");
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use fourier::*;
+use num_complex::Complex;
+use super::WindowFunction;
+
+/// Parameters of the spectrum analysis, sizes are given in samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpectrumAnalyzerParameters {
+    pub window_function: WindowFunction,
+    /// The number of samples analysed at once
+    pub fft_size: usize,
+    /// The number of new samples between two analyses
+    pub hop_size: usize,
+    /// The analysed samples are padded with zeros to `zero_padding` times their length
+    pub zero_padding: usize,
+}
+
+impl SpectrumAnalyzerParameters {
+    /// The smallest FFT size, for which the window functions are defined
+    pub const MIN_FFT_SIZE: usize = 2;
+
+    /// FFT sizes as used in properties, along with their descriptions.
+    /// `0` stands for the number of samples of a single video frame, which is resolved before the analyzer is created.
+    pub const FFT_SIZE_ITEMS: &'static [(&'static str, &'static str)] = &[
+        ("0", "Single Video Frame"),
+        ("64", "64"),
+        ("128", "128"),
+        ("256", "256"),
+        ("512", "512"),
+        ("1024", "1024"),
+        ("2048", "2048"),
+        ("4096", "4096"),
+        ("8192", "8192"),
+        ("16384", "16384"),
+        ("32768", "32768"),
+        ("65536", "65536"),
+    ];
+
+    /// Returns the FFT size of the given item name.
+    pub fn fft_size_from_name(name: &str) -> Option<usize> {
+        Self::FFT_SIZE_ITEMS.iter()
+            .find(|(item_name, _)| *item_name == name)
+            .and_then(|(item_name, _)| item_name.parse().ok())
+    }
+}
+
+/// The frequency spectrum of a single analysis, before any post-processing.
+#[derive(Clone)]
+pub struct RawSpectrum {
+    pub batch_number: usize,
+    /// The sample rate of the analysed samples
+    pub sample_rate: f64,
+    /// The time of the analysis in seconds, counted in samples received by the analyzer
+    pub time: f64,
+    pub magnitudes: Arc<Vec<f32>>,
+}
+
+/// Performs the FFT of the windowed samples, padded with zeros to the size of the FFT.
+/// Returns the magnitudes of the frequencies, which are scaled by the `SpectrumProcessor`.
+pub fn perform_analysis(
+    samples: impl Iterator<Item=f32> + ExactSizeIterator,
+    window: &[f32],
+    fft: &dyn Fft<Real = f32>,
+) -> Vec<f32> {
+    let padded_len = fft.size();
+
+    assert_eq!(samples.len(), window.len());
+    assert!(padded_len >= samples.len());
+
+    let len = samples.len();
+    let mut fft_data: Vec<Complex<f32>> = samples.zip(window.iter()).map(|(sample, window_coefficient)| {
+        Complex::new(sample * window_coefficient, 0.0)
+    }).collect::<Vec<_>>();
+
+    fft_data.resize(padded_len, Complex::new(0.0, 0.0));
+    fft.transform_in_place(&mut fft_data, Transform::Fft);
+
+    fft_data.into_iter().take(padded_len / 2).map(|complex| {
+        // normalize according to https://www.sjsu.edu/people/burford.furman/docs/me120/FFT_tutorial_NI.pdf
+        // the padding does not contribute any energy, so the number of actual samples is used
+        complex.norm() * 2.0 / len as f32
+    }).collect::<Vec<_>>()
+}
+
+/// Analyses the frequency spectrum of a stream of samples, regardless of where they come from.
+pub struct SpectrumAnalyzer {
+    parameters: SpectrumAnalyzerParameters,
+    sample_rate: f64,
+    /// The most recent samples, up to the FFT size
+    sample_buffer: VecDeque<f32>,
+    /// The number of samples received since the last analysis
+    samples_since_analysis: usize,
+    /// The number of samples received in total
+    samples_received: u64,
+    window: Vec<f32>,
+    /// The FFT plan, which is only created along with the analyzer
+    fft: Box<dyn Fft<Real = f32> + Send>,
+    result: Option<RawSpectrum>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(parameters: SpectrumAnalyzerParameters, sample_rate: f64) -> Self {
+        let parameters = SpectrumAnalyzerParameters {
+            fft_size: parameters.fft_size.max(SpectrumAnalyzerParameters::MIN_FFT_SIZE),
+            hop_size: parameters.hop_size.max(1),
+            zero_padding: parameters.zero_padding.max(1),
+            ..parameters
+        };
+
+        Self {
+            window: parameters.window_function.generate_coefficients(parameters.fft_size),
+            fft: fourier::create_fft_f32(parameters.fft_size * parameters.zero_padding),
+            sample_buffer: VecDeque::with_capacity(parameters.fft_size),
+            samples_since_analysis: 0,
+            samples_received: 0,
+            result: None,
+            parameters,
+            sample_rate,
+        }
+    }
+
+    pub fn parameters(&self) -> &SpectrumAnalyzerParameters {
+        &self.parameters
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The result of the most recent analysis.
+    pub fn result(&self) -> Option<&RawSpectrum> {
+        self.result.as_ref()
+    }
+
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        self.sample_buffer.extend(samples);
+        self.samples_since_analysis += samples.len();
+        self.samples_received += samples.len() as u64;
+
+        // Keep a sliding window of the most recent samples.
+        let samples_over_fft_size = self.sample_buffer.len().saturating_sub(self.parameters.fft_size);
+
+        if samples_over_fft_size > 0 {
+            self.sample_buffer.drain(0..samples_over_fft_size);
+        }
+    }
+
+    /// Whether the FFT size is reached and enough samples were received since the last analysis.
+    pub fn is_ready(&self) -> bool {
+        self.sample_buffer.len() == self.parameters.fft_size
+            && self.samples_since_analysis >= self.parameters.hop_size
+    }
+
+    /// Analyses the most recent samples, if the analyzer is ready.
+    /// Returns `true`, if the result was updated.
+    pub fn analyze(&mut self) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+
+        let magnitudes = perform_analysis(
+            self.sample_buffer.iter().copied(),
+            &self.window,
+            self.fft.as_ref(),
+        );
+        let next_batch_number = self.result.as_ref()
+            .map(|result| result.batch_number + 1).unwrap_or(0);
+
+        self.samples_since_analysis = 0;
+        self.result = Some(RawSpectrum {
+            batch_number: next_batch_number,
+            sample_rate: self.sample_rate,
+            time: self.samples_received as f64 / self.sample_rate,
+            magnitudes: Arc::new(magnitudes),
+        });
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{analyzer_parameters, peak_index, sine, SAMPLE_RATE};
+
+    #[test]
+    fn test_sine_peak() {
+        let mut analyzer = SpectrumAnalyzer::new(analyzer_parameters(1024, 1024), SAMPLE_RATE);
+        // Bin 32 of a 1024 sample FFT
+        let frequency = 32.0 * SAMPLE_RATE / 1024.0;
+
+        analyzer.push_samples(&sine(frequency, 0.5, 1024));
+        assert!(analyzer.analyze());
+
+        let result = analyzer.result().unwrap();
+
+        assert_eq!(result.batch_number, 0);
+        assert_eq!(result.magnitudes.len(), 512);
+        assert_eq!(peak_index(&result.magnitudes), 32);
+    }
+
+    #[test]
+    fn test_hop_size() {
+        let mut analyzer = SpectrumAnalyzer::new(analyzer_parameters(256, 128), SAMPLE_RATE);
+        let samples = sine(1000.0, 0.5, 1024);
+
+        // Not enough samples for a single analysis
+        analyzer.push_samples(&samples[..200]);
+        assert!(!analyzer.analyze());
+
+        analyzer.push_samples(&samples[200..256]);
+        assert!(analyzer.analyze());
+        assert!(!analyzer.analyze());
+
+        analyzer.push_samples(&samples[256..383]);
+        assert!(!analyzer.analyze());
+
+        analyzer.push_samples(&samples[383..384]);
+        assert!(analyzer.analyze());
+
+        let result = analyzer.result().unwrap();
+
+        assert_eq!(result.batch_number, 1);
+        assert_eq!(result.time, 384.0 / SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_tiny_fft_sizes() {
+        for &window_function in &[WindowFunction::Hanning, WindowFunction::None] {
+            for &fft_size in &[0, 1, 2] {
+                let mut analyzer = SpectrumAnalyzer::new(
+                    SpectrumAnalyzerParameters { window_function, ..analyzer_parameters(fft_size, 1) },
+                    SAMPLE_RATE,
+                );
+
+                analyzer.push_samples(&[0.5, -0.5]);
+                assert!(analyzer.analyze());
+                assert_eq!(analyzer.parameters().fft_size, 2);
+                assert_eq!(analyzer.result().unwrap().magnitudes.len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_size_items() {
+        assert_eq!(SpectrumAnalyzerParameters::fft_size_from_name("0"), Some(0));
+        assert_eq!(SpectrumAnalyzerParameters::fft_size_from_name("4096"), Some(4096));
+        assert_eq!(SpectrumAnalyzerParameters::fft_size_from_name("1"), None);
+        assert!(SpectrumAnalyzerParameters::FFT_SIZE_ITEMS.iter()
+            .filter_map(|(name, _)| name.parse::<usize>().ok())
+            .all(|fft_size| fft_size == 0 || fft_size.is_power_of_two()));
+    }
+}
@@ -0,0 +1,112 @@
+/// The number of pitch classes, starting at C
+pub const PITCH_CLASSES: usize = 12;
+/// The lowest frequency folded into the chroma vector, C1
+const F_MIN: f32 = 32.7;
+/// The highest frequency folded into the chroma vector, C8
+const F_MAX: f32 = 4186.0;
+
+/// Folds the bins of a frequency spectrum into the energies of the 12 pitch classes.
+pub struct ChromaFilterBank {
+    /// The pitch class of each bin, `None` for bins outside of the covered range
+    pitch_classes: Vec<Option<usize>>,
+    fft_size: usize,
+    sample_rate: f32,
+}
+
+impl ChromaFilterBank {
+    pub fn new(fft_size: usize, sample_rate: f32) -> Self {
+        let pitch_classes = (0..(fft_size / 2)).map(|bin| {
+            let frequency = bin as f32 * sample_rate / fft_size as f32;
+
+            if !(F_MIN..=F_MAX).contains(&frequency) {
+                return None;
+            }
+
+            // The MIDI note number, A4 at 440 Hz is note 69 and C is a multiple of 12
+            let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+
+            Some((note.round() as usize) % PITCH_CLASSES)
+        }).collect();
+
+        Self {
+            pitch_classes,
+            fft_size,
+            sample_rate,
+        }
+    }
+
+    /// The FFT size the filter bank was created for
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Sums the energy of the bins of each pitch class, normalized so that the strongest pitch class is `1`.
+    pub fn apply(&self, fft_magnitudes: &[f32]) -> Vec<f32> {
+        let mut chroma = vec![0.0; PITCH_CLASSES];
+
+        for (pitch_class, magnitude) in self.pitch_classes.iter().zip(fft_magnitudes) {
+            if let Some(pitch_class) = pitch_class {
+                chroma[*pitch_class] += magnitude * magnitude;
+            }
+        }
+
+        let max = chroma.iter().copied().fold(0.0, f32::max);
+
+        // Silence stays at zero, instead of dividing by zero
+        if max > 1e-12 {
+            chroma.iter_mut().for_each(|energy| *energy /= max);
+        }
+
+        chroma
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{peak_index, sines, spectrum, SAMPLE_RATE};
+
+    const FFT_SIZE: usize = 4096;
+
+    fn chroma_of_sines(frequencies: &[f64]) -> Vec<f32> {
+        let components = frequencies.iter().map(|&frequency| (frequency, 0.25)).collect::<Vec<_>>();
+        let magnitudes = spectrum(&sines(&components, FFT_SIZE), FFT_SIZE);
+
+        ChromaFilterBank::new(FFT_SIZE, SAMPLE_RATE as f32).apply(&magnitudes)
+    }
+
+    #[test]
+    fn test_pitch_classes_of_sines() {
+        // A4, C4 and E5
+        assert_eq!(peak_index(&chroma_of_sines(&[440.0])), 9);
+        assert_eq!(peak_index(&chroma_of_sines(&[261.63])), 0);
+        assert_eq!(peak_index(&chroma_of_sines(&[659.26])), 4);
+
+        let chroma = chroma_of_sines(&[440.0]);
+
+        assert_eq!(chroma.len(), PITCH_CLASSES);
+        assert_eq!(chroma[9], 1.0);
+    }
+
+    #[test]
+    fn test_chord() {
+        // A C major chord contains the pitch classes C, E and G
+        let chroma = chroma_of_sines(&[261.63, 329.63, 392.0]);
+        let mut pitch_classes = (0..PITCH_CLASSES).collect::<Vec<_>>();
+
+        pitch_classes.sort_by(|&a, &b| chroma[b].partial_cmp(&chroma[a]).unwrap());
+        pitch_classes.truncate(3);
+        pitch_classes.sort_unstable();
+
+        assert_eq!(pitch_classes, vec![0, 4, 7]);
+    }
+
+    #[test]
+    fn test_silence() {
+        assert_eq!(chroma_of_sines(&[]), vec![0.0; PITCH_CLASSES]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+
+/// The center frequency all bands are aligned to, so that 3 bands per octave yield the standard 1/3-octave bands
+const REFERENCE_FREQUENCY: f32 = 1000.0;
+/// The lowest frequency the bands may start at, as the band indices are derived from the logarithm of the frequency
+pub const MIN_FREQUENCY: f32 = 1.0;
+
+/// Parameters of the logarithmically spaced frequency bands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFrequencyParameters {
+    pub bins_per_octave: usize,
+    pub f_min: f32,
+    pub f_max: f32,
+}
+
+impl LogFrequencyParameters {
+    /// Checks that the frequency range starts at `MIN_FREQUENCY` or above and is not empty.
+    pub fn validate(&self) -> Result<(), Cow<'static, str>> {
+        if self.f_min.is_nan() || self.f_min < MIN_FREQUENCY {
+            return Err(format!("`log_f_min` must be at least {} Hz, but is {} Hz.", MIN_FREQUENCY, self.f_min).into());
+        }
+
+        if self.f_max.is_nan() || self.f_max <= self.f_min {
+            return Err(format!("`log_f_max` ({} Hz) must be greater than `log_f_min` ({} Hz).", self.f_max, self.f_min).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A frequency band, which spans a constant fraction of an octave
+#[derive(Debug, Clone, PartialEq)]
+struct LogFrequencyBand {
+    center_frequency: f32,
+    /// The first bin within the band
+    start_bin: usize,
+    /// The bin after the last bin within the band, equal to `start_bin` if the band is narrower than a bin
+    end_bin: usize,
+    /// The fractional bin of the center frequency, to interpolate bands narrower than a bin
+    center_bin: f32,
+}
+
+/// Maps the bins of a linear frequency spectrum to logarithmically spaced frequency bands.
+pub struct LogFrequencyFilterBank {
+    bands: Vec<LogFrequencyBand>,
+    fft_size: usize,
+    sample_rate: f32,
+}
+
+impl LogFrequencyFilterBank {
+    /// Creates the bands overlapping the frequency range, with center frequencies of `1000 * 2^(k / bins_per_octave)` Hz.
+    /// A range failing `LogFrequencyParameters::validate` is clamped, so that at least one band is created.
+    pub fn new(fft_size: usize, sample_rate: f32, parameters: &LogFrequencyParameters) -> Self {
+        let bins_per_octave = parameters.bins_per_octave.max(1) as f32;
+        let f_min = parameters.f_min.max(MIN_FREQUENCY);
+        // Frequencies above the sample rate cannot be present in the spectrum
+        let f_max = parameters.f_max.min(sample_rate).max(f_min);
+        let n_fft_bins = fft_size / 2;
+        let bin_width = sample_rate / fft_size as f32;
+        // The factor between the center frequency and the band edges
+        let half_band = 2f32.powf(0.5 / bins_per_octave);
+        let k_min = (bins_per_octave * (f_min / half_band / REFERENCE_FREQUENCY).log2()).floor() as i32;
+        let k_max = (bins_per_octave * (f_max * half_band / REFERENCE_FREQUENCY).log2()).ceil() as i32;
+        let center_frequency = |k: i32| REFERENCE_FREQUENCY * 2f32.powf(k as f32 / bins_per_octave);
+        let band = |center_frequency: f32| {
+            let start_bin = ((center_frequency / half_band / bin_width).ceil() as usize).min(n_fft_bins);
+            let end_bin = ((center_frequency * half_band / bin_width).ceil() as usize).min(n_fft_bins).max(start_bin);
+
+            LogFrequencyBand {
+                center_frequency,
+                start_bin,
+                end_bin,
+                center_bin: center_frequency / bin_width,
+            }
+        };
+        let mut bands = (k_min..=k_max)
+            .map(center_frequency)
+            .filter(|&center_frequency| center_frequency * half_band > f_min && center_frequency / half_band < f_max)
+            .map(band)
+            .collect::<Vec<_>>();
+
+        if bands.is_empty() {
+            // The range is a single frequency at the edge of two bands
+            let k = (bins_per_octave * (f_min / REFERENCE_FREQUENCY).log2()).round() as i32;
+
+            bands.push(band(center_frequency(k)));
+        }
+
+        Self {
+            bands,
+            fft_size,
+            sample_rate,
+        }
+    }
+
+    /// The FFT size the filter bank was created for
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// The center frequencies of the bands in Hz
+    pub fn center_frequencies(&self) -> impl Iterator<Item=f32> + '_ {
+        self.bands.iter().map(|band| band.center_frequency)
+    }
+
+    /// Maps the magnitudes to the bands. The energies of the bins within a band are summed up,
+    /// so that pink noise has the same magnitude in all bands. Bands narrower than a bin are interpolated.
+    pub fn apply(&self, fft_magnitudes: &[f32]) -> Vec<f32> {
+        self.bands.iter().map(|band| {
+            if band.end_bin > band.start_bin {
+                fft_magnitudes[band.start_bin..band.end_bin.min(fft_magnitudes.len())].iter()
+                    .map(|magnitude| magnitude * magnitude)
+                    .sum::<f32>()
+                    .sqrt()
+            } else {
+                let lower_bin = band.center_bin.floor() as usize;
+                let fraction = band.center_bin - lower_bin as f32;
+                let magnitude = |bin: usize| fft_magnitudes.get(bin).copied().unwrap_or(0.0);
+
+                magnitude(lower_bin) * (1.0 - fraction) + magnitude(lower_bin + 1) * fraction
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::peak_index;
+
+    const FFT_SIZE: usize = 4096;
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn third_octave_bands() -> LogFrequencyFilterBank {
+        LogFrequencyFilterBank::new(FFT_SIZE, SAMPLE_RATE, &LogFrequencyParameters {
+            bins_per_octave: 3,
+            f_min: 20.0,
+            f_max: 20000.0,
+        })
+    }
+
+    #[test]
+    fn test_third_octave_bands() {
+        let filter_bank = third_octave_bands();
+        let center_frequencies = filter_bank.center_frequencies().collect::<Vec<_>>();
+
+        // The 31 standard bands with nominal center frequencies from 20 Hz to 20 kHz
+        assert_eq!(center_frequencies.len(), 31);
+        assert!((center_frequencies[0] - 19.69).abs() < 0.01);
+        assert!((center_frequencies[17] - 1000.0).abs() < 0.01);
+        assert!((center_frequencies[30] - 20158.74).abs() < 0.1);
+
+        // The bands do not overlap
+        for (band, next_band) in filter_bank.bands.iter().zip(&filter_bank.bands[1..]) {
+            assert!(band.end_bin <= next_band.start_bin);
+        }
+    }
+
+    #[test]
+    fn test_sine_peaks() {
+        let filter_bank = third_octave_bands();
+
+        for &(frequency, expected_band) in &[(1000.0, 17), (100.0, 7), (8000.0, 26)] {
+            let mut magnitudes = vec![0.0; FFT_SIZE / 2];
+            let bin = (frequency * FFT_SIZE as f32 / SAMPLE_RATE).round() as usize;
+
+            magnitudes[bin] = 1.0;
+
+            let bands = filter_bank.apply(&magnitudes);
+
+            assert_eq!(bands.len(), 31);
+            assert_eq!(peak_index(&bands), expected_band);
+        }
+    }
+
+    #[test]
+    fn test_narrow_bands_are_interpolated() {
+        // With 24 bands per octave, the bands below 500 Hz are narrower than a bin
+        let filter_bank = LogFrequencyFilterBank::new(FFT_SIZE, SAMPLE_RATE, &LogFrequencyParameters {
+            bins_per_octave: 24,
+            f_min: 20.0,
+            f_max: 20000.0,
+        });
+        let magnitudes = vec![0.5; FFT_SIZE / 2];
+        let bands = filter_bank.apply(&magnitudes);
+
+        assert!(bands.iter().all(|magnitude| *magnitude >= 0.5 - 1e-6));
+        assert!(bands[..10].iter().all(|magnitude| (*magnitude - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_invalid_frequency_ranges() {
+        let ranges = [
+            (0.0, 20000.0),
+            (-10.0, 100.0),
+            (1000.0, 1000.0),
+            (2000.0, 1000.0),
+            (f32::NAN, 1000.0),
+            (100.0, f32::NAN),
+        ];
+
+        for &(f_min, f_max) in &ranges {
+            let parameters = LogFrequencyParameters {
+                bins_per_octave: 3,
+                f_min,
+                f_max,
+            };
+            let filter_bank = LogFrequencyFilterBank::new(FFT_SIZE, SAMPLE_RATE, &parameters);
+
+            assert!(parameters.validate().is_err());
+            assert!(filter_bank.center_frequencies().count() >= 1);
+            assert!(filter_bank.center_frequencies().all(|frequency| frequency >= MIN_FREQUENCY / 2.0));
+        }
+
+        assert!(LogFrequencyParameters { bins_per_octave: 3, f_min: 20.0, f_max: 20000.0 }.validate().is_ok());
+
+        // The edge of two bands
+        let edge = REFERENCE_FREQUENCY * 2f32.powf(0.5 / 3.0);
+        let filter_bank = LogFrequencyFilterBank::new(FFT_SIZE, SAMPLE_RATE, &LogFrequencyParameters {
+            bins_per_octave: 3,
+            f_min: edge,
+            f_max: edge,
+        });
+
+        assert_eq!(filter_bank.center_frequencies().count(), 1);
+    }
+}
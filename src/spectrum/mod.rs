@@ -0,0 +1,14 @@
+mod window;
+mod analyzer;
+mod chroma;
+mod log_frequency;
+mod processor;
+mod scale;
+
+// Re-export the public API
+pub use window::WindowFunction;
+pub use analyzer::{perform_analysis, RawSpectrum, SpectrumAnalyzer, SpectrumAnalyzerParameters};
+pub use chroma::ChromaFilterBank;
+pub use log_frequency::{LogFrequencyFilterBank, LogFrequencyParameters};
+pub use processor::{FFTResult, FrequencyMapping, SpectrumProcessor, SpectrumProcessorParameters};
+pub use scale::SpectrumScale;
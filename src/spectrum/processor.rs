@@ -0,0 +1,511 @@
+use std::sync::Arc;
+use crate::mel::{self, MelFilterBank, MelParameters};
+use super::{ChromaFilterBank, LogFrequencyFilterBank, LogFrequencyParameters, RawSpectrum, SpectrumScale};
+
+/// Parameters of the post-processing, which may differ between consumers of the same raw spectrum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpectrumProcessorParameters {
+    pub dampening_factor_attack: f64,
+    pub dampening_factor_release: f64,
+    /// The scale of the frequency spectrum and the logarithmic frequency spectrum
+    pub scale: SpectrumScale,
+    /// The spectrum of logarithmically spaced frequency bands is computed as well, if set
+    pub log: Option<LogFrequencyParameters>,
+    /// The Mel spectrum is computed as well, if set
+    pub mel: Option<MelParameters>,
+    /// The scale of the Mel spectrum, which is computed from the unscaled magnitudes
+    pub mel_scale: SpectrumScale,
+    /// The number of Mel-frequency cepstral coefficients to compute, requires `mel` to be set
+    pub mfcc_coefficients: Option<usize>,
+    /// Whether the energies of the 12 pitch classes are computed
+    pub chroma: bool,
+}
+
+/// Which of the spectra of an `FFTResult` is used, the discriminant is passed to shaders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrequencyMapping {
+    Linear = 0,
+    Mel = 1,
+    /// Logarithmically spaced frequency bands
+    Log = 2,
+}
+
+impl FrequencyMapping {
+    /// Names of the frequency mappings as used in properties, along with their descriptions.
+    pub const ITEMS: &'static [(&'static str, &'static str)] = &[
+        ("linear", "Linear"),
+        ("mel", "Mel"),
+        ("log", "Logarithmic"),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" => FrequencyMapping::Linear,
+            "mel" => FrequencyMapping::Mel,
+            "log" => FrequencyMapping::Log,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct FFTResult {
+    pub batch_number: usize,
+    /// The sample rate of the analysed samples
+    pub sample_rate: f64,
+    /// The time of the analysis in seconds, see `RawSpectrum::time`
+    pub time: f64,
+    pub frequency_spectrum: Arc<Vec<f32>>,
+    pub mel_spectrum: Option<Arc<Vec<f32>>>,
+    /// The center frequencies of the first and the last Mel band
+    pub mel_frequency_range: Option<[f32; 2]>,
+    /// The spectrum of logarithmically spaced frequency bands
+    pub log_spectrum: Option<Arc<Vec<f32>>>,
+    /// The center frequencies of the first and the last logarithmic frequency band
+    pub log_frequency_range: Option<[f32; 2]>,
+    pub mfcc: Option<Arc<Vec<f32>>>,
+    /// The energies of the pitch classes, starting at C
+    pub chroma: Option<Arc<Vec<f32>>>,
+}
+
+/// Dampens raw spectra over time and derives the Mel spectrum and other features from them.
+pub struct SpectrumProcessor {
+    parameters: SpectrumProcessorParameters,
+    /// The most recently processed raw spectrum
+    previous_raw_spectrum: Option<RawSpectrum>,
+    /// Rebuilt only when the FFT size or the sample rate changes
+    mel_filter_bank: Option<MelFilterBank>,
+    /// Rebuilt only when the FFT size or the sample rate changes
+    log_filter_bank: Option<LogFrequencyFilterBank>,
+    /// Rebuilt only when the FFT size or the sample rate changes
+    chroma_filter_bank: Option<ChromaFilterBank>,
+    result: Option<FFTResult>,
+}
+
+impl FFTResult {
+    /// The spectrum of the given frequency mapping, or the linear spectrum if it was not computed.
+    pub fn spectrum(&self, frequency_mapping: FrequencyMapping) -> &Arc<Vec<f32>> {
+        match frequency_mapping {
+            FrequencyMapping::Linear => None,
+            FrequencyMapping::Mel => self.mel_spectrum.as_ref(),
+            FrequencyMapping::Log => self.log_spectrum.as_ref(),
+        }.unwrap_or(&self.frequency_spectrum)
+    }
+
+    /// The frequencies in Hz of the first and the last value of `spectrum(frequency_mapping)`.
+    pub fn frequency_range(&self, frequency_mapping: FrequencyMapping) -> [f32; 2] {
+        match frequency_mapping {
+            FrequencyMapping::Linear => None,
+            FrequencyMapping::Mel => self.mel_frequency_range,
+            FrequencyMapping::Log => self.log_frequency_range,
+        }.unwrap_or_else(|| {
+            // The spectrum covers the frequencies up to half the sample rate
+            let bins = self.frequency_spectrum.len();
+            let bin_width = self.sample_rate as f32 / (2 * bins.max(1)) as f32;
+
+            [0.0, bins.saturating_sub(1) as f32 * bin_width]
+        })
+    }
+}
+
+impl SpectrumProcessor {
+    pub fn new(parameters: SpectrumProcessorParameters) -> Self {
+        Self {
+            parameters,
+            previous_raw_spectrum: None,
+            mel_filter_bank: None,
+            log_filter_bank: None,
+            chroma_filter_bank: None,
+            result: None,
+        }
+    }
+
+    pub fn parameters(&self) -> &SpectrumProcessorParameters {
+        &self.parameters
+    }
+
+    /// The result of the most recent processing.
+    pub fn result(&self) -> Option<&FFTResult> {
+        self.result.as_ref()
+    }
+
+    /// Processes the raw spectrum, unless it is the one processed most recently.
+    pub fn process(&mut self, raw_spectrum: &RawSpectrum) -> &FFTResult {
+        let already_processed = self.previous_raw_spectrum.as_ref().map(|previous_raw_spectrum| {
+            Arc::ptr_eq(&previous_raw_spectrum.magnitudes, &raw_spectrum.magnitudes)
+        }).unwrap_or(false);
+
+        if !already_processed {
+            self.result = Some(self.process_new(raw_spectrum));
+            self.previous_raw_spectrum = Some(raw_spectrum.clone());
+        }
+
+        self.result.as_ref().unwrap()
+    }
+
+    /// Returns the Mel filter bank for spectra of the given length, creating it if necessary.
+    fn mel_filter_bank(&mut self, spectrum_len: usize, sample_rate: f32) -> Option<&MelFilterBank> {
+        let mel_parameters = self.parameters.mel.as_ref()?;
+
+        if spectrum_len == 0 {
+            return None;
+        }
+
+        // The spectrum contains half as many bins as the padded FFT size
+        let fft_size = spectrum_len * 2;
+        let outdated = self.mel_filter_bank.as_ref().map(|filter_bank| {
+            filter_bank.fft_size() != fft_size || filter_bank.sample_rate() != sample_rate
+        }).unwrap_or(true);
+
+        if outdated {
+            self.mel_filter_bank = Some(MelFilterBank::from_parameters(fft_size, sample_rate, mel_parameters.clone()));
+        }
+
+        self.mel_filter_bank.as_ref()
+    }
+
+    /// Returns the logarithmic frequency filter bank for spectra of the given length, creating it if necessary.
+    fn log_filter_bank(&mut self, spectrum_len: usize, sample_rate: f32) -> Option<&LogFrequencyFilterBank> {
+        let log_parameters = self.parameters.log.as_ref()?;
+
+        if spectrum_len == 0 {
+            return None;
+        }
+
+        let fft_size = spectrum_len * 2;
+        let outdated = self.log_filter_bank.as_ref().map(|filter_bank| {
+            filter_bank.fft_size() != fft_size || filter_bank.sample_rate() != sample_rate
+        }).unwrap_or(true);
+
+        if outdated {
+            self.log_filter_bank = Some(LogFrequencyFilterBank::new(fft_size, sample_rate, log_parameters));
+        }
+
+        self.log_filter_bank.as_ref()
+    }
+
+    /// Returns the chroma filter bank for spectra of the given length, creating it if necessary.
+    fn chroma_filter_bank(&mut self, spectrum_len: usize, sample_rate: f32) -> Option<&ChromaFilterBank> {
+        if !self.parameters.chroma || spectrum_len == 0 {
+            return None;
+        }
+
+        let fft_size = spectrum_len * 2;
+        let outdated = self.chroma_filter_bank.as_ref().map(|filter_bank| {
+            filter_bank.fft_size() != fft_size || filter_bank.sample_rate() != sample_rate
+        }).unwrap_or(true);
+
+        if outdated {
+            self.chroma_filter_bank = Some(ChromaFilterBank::new(fft_size, sample_rate));
+        }
+
+        self.chroma_filter_bank.as_ref()
+    }
+
+    /// Mixes the current values with the previous ones, depending on the time elapsed since the previous spectrum.
+    fn dampen(&self, current: &mut [f32], previous: &[f32], time_elapsed: f64) {
+        if current.len() != previous.len() {
+            return;
+        }
+
+        let dampening_multiplier_attack = self.parameters.dampening_factor_attack.powf(
+            time_elapsed
+        ).clamp(0.0, 1.0) as f32;
+        let dampening_multiplier_release = self.parameters.dampening_factor_release.powf(
+            time_elapsed
+        ).clamp(0.0, 1.0) as f32;
+
+        current.iter_mut()
+            .zip(previous.iter())
+            .for_each(move |(current, previous)| {
+                let dampening_multiplier = if *current > *previous {
+                    dampening_multiplier_attack
+                } else {
+                    dampening_multiplier_release
+                };
+
+                *current = dampening_multiplier * *previous + (1.0 - dampening_multiplier) * *current;
+            })
+    }
+
+    fn process_new(&mut self, raw_spectrum: &RawSpectrum) -> FFTResult {
+        let mut frequency_spectrum = raw_spectrum.magnitudes.as_ref().clone();
+
+        self.parameters.scale.apply(&mut frequency_spectrum);
+
+        // Compute Mel spectrogram and MFCCs from the unscaled magnitudes, if enabled
+        let mel_scale = self.parameters.mel_scale;
+        let mfcc_coefficients = self.parameters.mfcc_coefficients;
+        let (mut mel_spectrum, mel_frequency_range, mut mfcc) = self.mel_filter_bank(frequency_spectrum.len(), raw_spectrum.sample_rate as f32)
+            .map(|filter_bank| {
+                let mut mel_values = filter_bank.apply(&raw_spectrum.magnitudes);
+                let mfcc = mfcc_coefficients.map(|n_coefficients| {
+                    mel::mfcc(filter_bank, &raw_spectrum.magnitudes, n_coefficients)
+                });
+
+                mel_scale.apply(&mut mel_values);
+                (Some(mel_values), first_and_last(filter_bank.center_frequencies().iter().copied()), mfcc)
+            })
+            .unwrap_or((None, None, None));
+        let scale = self.parameters.scale;
+        let (mut log_spectrum, log_frequency_range) = self.log_filter_bank(frequency_spectrum.len(), raw_spectrum.sample_rate as f32)
+            .map(|filter_bank| {
+                let mut log_values = filter_bank.apply(&raw_spectrum.magnitudes);
+
+                scale.apply(&mut log_values);
+                (Some(log_values), first_and_last(filter_bank.center_frequencies()))
+            })
+            .unwrap_or((None, None));
+        let mut chroma = self.chroma_filter_bank(frequency_spectrum.len(), raw_spectrum.sample_rate as f32)
+            .map(|filter_bank| filter_bank.apply(&raw_spectrum.magnitudes));
+
+        // Dampen the result by mixing it with the result from the previous batch
+        if self.parameters.dampening_factor_attack > 0.0 || self.parameters.dampening_factor_release > 0.0 {
+            let previous = self.result.as_ref().zip(self.previous_raw_spectrum.as_ref())
+                .filter(|(_, previous_raw_spectrum)| previous_raw_spectrum.time < raw_spectrum.time);
+
+            if let Some((previous_result, previous_raw_spectrum)) = previous {
+                let time_elapsed = raw_spectrum.time - previous_raw_spectrum.time;
+
+                self.dampen(&mut frequency_spectrum, &previous_result.frequency_spectrum, time_elapsed);
+
+                let features = vec![
+                    (&mut mel_spectrum, &previous_result.mel_spectrum),
+                    (&mut log_spectrum, &previous_result.log_spectrum),
+                    (&mut mfcc, &previous_result.mfcc),
+                    (&mut chroma, &previous_result.chroma),
+                ];
+
+                for (current, previous) in features {
+                    if let Some((current, previous)) = current.as_mut().zip(previous.as_ref()) {
+                        self.dampen(current, previous, time_elapsed);
+                    }
+                }
+            }
+        }
+
+        FFTResult {
+            batch_number: raw_spectrum.batch_number,
+            sample_rate: raw_spectrum.sample_rate,
+            time: raw_spectrum.time,
+            frequency_spectrum: Arc::new(frequency_spectrum),
+            mel_spectrum: mel_spectrum.map(Arc::new),
+            mel_frequency_range,
+            log_spectrum: log_spectrum.map(Arc::new),
+            log_frequency_range,
+            mfcc: mfcc.map(Arc::new),
+            chroma: chroma.map(Arc::new),
+        }
+    }
+}
+
+/// The first and the last of the frequencies, if there are any.
+fn first_and_last(mut frequencies: impl Iterator<Item=f32>) -> Option<[f32; 2]> {
+    let first = frequencies.next()?;
+
+    Some([first, frequencies.last().unwrap_or(first)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mel::MelFormula;
+    use crate::spectrum::{SpectrumAnalyzer, SpectrumAnalyzerParameters};
+    use crate::test_util::{analyzer_parameters, SAMPLE_RATE};
+
+    fn parameters(dampening_factor: f64) -> SpectrumProcessorParameters {
+        SpectrumProcessorParameters {
+            dampening_factor_attack: dampening_factor,
+            dampening_factor_release: dampening_factor,
+            scale: SpectrumScale::Magnitude,
+            log: None,
+            mel: None,
+            mel_scale: SpectrumScale::Normalized,
+            mfcc_coefficients: None,
+            chroma: false,
+        }
+    }
+
+    fn raw_spectrum(batch_number: usize, time: f64, magnitudes: Vec<f32>) -> RawSpectrum {
+        RawSpectrum {
+            batch_number,
+            sample_rate: SAMPLE_RATE,
+            time,
+            magnitudes: Arc::new(magnitudes),
+        }
+    }
+
+    #[test]
+    fn test_dampening() {
+        let mut processor = SpectrumProcessor::new(parameters(0.25));
+
+        processor.process(&raw_spectrum(0, 0.5, vec![0.0, 1.0]));
+
+        // Half a second elapsed, so the previous result is weighted by 0.25^0.5
+        let result = processor.process(&raw_spectrum(1, 1.0, vec![1.0, 0.0]));
+
+        assert_eq!(result.batch_number, 1);
+        assert_eq!(*result.frequency_spectrum, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_consumers_with_different_parameters() {
+        let mut undampened = SpectrumProcessor::new(parameters(0.0));
+        let mut dampened = SpectrumProcessor::new(parameters(0.25));
+        let first = raw_spectrum(0, 0.5, vec![0.0]);
+        let second = raw_spectrum(1, 1.0, vec![1.0]);
+
+        for raw_spectrum in &[&first, &second, &second] {
+            undampened.process(raw_spectrum);
+            dampened.process(raw_spectrum);
+        }
+
+        // The raw spectrum is shared and processing the same spectrum twice has no effect
+        assert_eq!(*first.magnitudes, vec![0.0]);
+        assert_eq!(*undampened.result().unwrap().frequency_spectrum, vec![1.0]);
+        assert_eq!(*dampened.result().unwrap().frequency_spectrum, vec![0.5]);
+    }
+
+    #[test]
+    fn test_mel_spectrum() {
+        let mut processor = SpectrumProcessor::new(SpectrumProcessorParameters {
+            mel: Some(MelParameters {
+                n_mels: 40,
+                f_min: 20.0,
+                f_max: 8000.0,
+                formula: MelFormula::Htk,
+                area_normalization: false,
+            }),
+            ..parameters(0.0)
+        });
+        let mut magnitudes = vec![0.0; 512];
+
+        magnitudes[21] = 1.0;
+
+        let result = processor.process(&raw_spectrum(0, 0.0, magnitudes));
+        let mel_spectrum = result.mel_spectrum.clone().unwrap();
+        let [f_first, f_last] = result.frequency_range(FrequencyMapping::Mel);
+
+        assert_eq!(mel_spectrum.len(), 40);
+        assert!(mel_spectrum.iter().all(|value| (0.0..=1.0).contains(value)));
+        // The centers of the outer bands lie within the frequency range
+        assert!(20.0 < f_first && f_first < f_last && f_last < 8000.0);
+    }
+
+    #[test]
+    fn test_filter_bank_fft_size() {
+        for &zero_padding in &[1, 2] {
+            let mut analyzer = SpectrumAnalyzer::new(
+                SpectrumAnalyzerParameters { zero_padding, ..analyzer_parameters(1024, 1024) },
+                SAMPLE_RATE,
+            );
+            let mut processor = SpectrumProcessor::new(SpectrumProcessorParameters {
+                mel: Some(MelParameters {
+                    n_mels: 40,
+                    f_min: 20.0,
+                    f_max: 8000.0,
+                    formula: MelFormula::Htk,
+                    area_normalization: false,
+                }),
+                ..parameters(0.0)
+            });
+
+            analyzer.push_samples(&vec![0.0; 1024]);
+            assert!(analyzer.analyze());
+            processor.process(analyzer.result().unwrap());
+
+            let fft_size = analyzer.parameters().fft_size * analyzer.parameters().zero_padding;
+
+            assert_eq!(processor.mel_filter_bank.as_ref().unwrap().fft_size(), fft_size);
+        }
+
+        let mut processor = SpectrumProcessor::new(SpectrumProcessorParameters {
+            mel: Some(MelParameters {
+                n_mels: 40,
+                f_min: 20.0,
+                f_max: 8000.0,
+                formula: MelFormula::Htk,
+                area_normalization: false,
+            }),
+            chroma: true,
+            ..parameters(0.0)
+        });
+        let result = processor.process(&raw_spectrum(0, 0.0, Vec::new()));
+
+        assert!(result.mel_spectrum.is_none());
+        assert!(result.chroma.is_none());
+    }
+
+    #[test]
+    fn test_scales() {
+        let mut processor = SpectrumProcessor::new(SpectrumProcessorParameters {
+            scale: SpectrumScale::SqrtMagnitude,
+            mel: Some(MelParameters {
+                n_mels: 8,
+                f_min: 0.0,
+                f_max: 24000.0,
+                formula: MelFormula::Slaney,
+                area_normalization: false,
+            }),
+            mel_scale: SpectrumScale::Decibels {
+                floor: (-80.0).into(),
+                ceiling: 0.0.into(),
+            },
+            ..parameters(0.0)
+        });
+        let result = processor.process(&raw_spectrum(0, 0.0, vec![0.0; 512]));
+
+        // Silence is at the floor of the fixed range, rather than normalized to the middle
+        assert_eq!(*result.frequency_spectrum, vec![0.0; 512]);
+        assert_eq!(*result.mel_spectrum.clone().unwrap(), vec![0.0; 8]);
+
+        let result = processor.process(&raw_spectrum(1, 0.1, vec![0.5; 512]));
+
+        assert!(result.frequency_spectrum.iter().all(|value| (value - 1.0).abs() < 1e-6));
+        assert!(result.mel_spectrum.as_ref().unwrap().iter().any(|value| *value > 0.0));
+    }
+
+    #[test]
+    fn test_features() {
+        let mut processor = SpectrumProcessor::new(SpectrumProcessorParameters {
+            mel: Some(MelParameters {
+                n_mels: 40,
+                f_min: 0.0,
+                f_max: 24000.0,
+                formula: MelFormula::Htk,
+                area_normalization: false,
+            }),
+            log: Some(LogFrequencyParameters {
+                bins_per_octave: 3,
+                f_min: 20.0,
+                f_max: 20000.0,
+            }),
+            mfcc_coefficients: Some(13),
+            chroma: true,
+            ..parameters(0.5)
+        });
+        let mut magnitudes = vec![0.0; 512];
+
+        // 656.25 Hz is closest to E5
+        magnitudes[14] = 1.0;
+
+        let result = processor.process(&raw_spectrum(0, 0.0, magnitudes)).clone();
+        let chroma = result.chroma.clone().unwrap();
+        let log_spectrum = result.log_spectrum.clone().unwrap();
+
+        // The 31 standard 1/3-octave bands, 656.25 Hz is in the band centered at 630 Hz
+        assert_eq!(log_spectrum.len(), 31);
+        assert_eq!(log_spectrum[15], 1.0);
+        assert!(Arc::ptr_eq(result.spectrum(FrequencyMapping::Log), &log_spectrum));
+        assert!((result.frequency_range(FrequencyMapping::Log)[0] - 19.69).abs() < 0.01);
+        assert_eq!(result.frequency_range(FrequencyMapping::Linear), [0.0, 511.0 * 46.875]);
+        assert_eq!(result.mfcc.as_ref().unwrap().len(), 13);
+        assert_eq!(chroma.len(), 12);
+        assert_eq!(chroma[4], 1.0);
+
+        // The features are dampened like the spectrum
+        let chroma = processor.process(&raw_spectrum(1, 1.0, vec![0.0; 512])).chroma.clone().unwrap();
+
+        assert_eq!(chroma[4], 0.5);
+    }
+}
@@ -0,0 +1,139 @@
+use ordered_float::OrderedFloat;
+
+/// Added to magnitudes before taking the logarithm, to avoid log(0)
+const DECIBEL_OFFSET: f32 = 1e-10;
+
+/// How the magnitudes of a spectrum are mapped to the values passed to the shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpectrumScale {
+    /// The square root of twice the magnitude, which is the scale used before the scales were configurable
+    SqrtMagnitude,
+    /// The amplitude of the frequency
+    Magnitude,
+    /// The squared magnitude
+    Power,
+    /// Decibels mapped from the range [floor; ceiling] to [0; 1], values outside of the range are clamped
+    Decibels {
+        floor: OrderedFloat<f32>,
+        ceiling: OrderedFloat<f32>,
+    },
+    /// Decibels mapped from the range of each individual spectrum to [0; 1]
+    Normalized,
+}
+
+impl SpectrumScale {
+    /// Names of the scales as used in properties, along with their descriptions.
+    pub const ITEMS: &'static [(&'static str, &'static str)] = &[
+        ("sqrt_magnitude", "Square Root of Magnitude"),
+        ("magnitude", "Magnitude"),
+        ("power", "Power"),
+        ("decibels", "Decibels (Fixed Range)"),
+        ("normalized", "Decibels (Normalized per Frame)"),
+    ];
+
+    /// Returns the scale of the given name, the decibel range is only used by `decibels`.
+    pub fn from_name(name: &str, decibel_range: [f32; 2]) -> Option<Self> {
+        use SpectrumScale::*;
+
+        let [floor, ceiling] = decibel_range;
+
+        Some(match name {
+            "sqrt_magnitude" => SqrtMagnitude,
+            "magnitude" => Magnitude,
+            "power" => Power,
+            "decibels" => Decibels {
+                floor: OrderedFloat(floor),
+                ceiling: OrderedFloat(ceiling),
+            },
+            "normalized" => Normalized,
+            _ => return None,
+        })
+    }
+
+    /// Maps the magnitudes of a spectrum in place.
+    pub fn apply(self, values: &mut [f32]) {
+        use SpectrumScale::*;
+
+        match self {
+            SqrtMagnitude => values.iter_mut().for_each(|value| *value = (2.0 * *value).sqrt()),
+            Magnitude => (),
+            Power => values.iter_mut().for_each(|value| *value = *value * *value),
+            Decibels { floor, ceiling } => {
+                let (floor, ceiling) = (*floor, *ceiling);
+
+                values.iter_mut().for_each(|value| {
+                    let decibels = to_decibels(*value);
+
+                    *value = if ceiling > floor {
+                        ((decibels - floor) / (ceiling - floor)).clamp(0.0, 1.0)
+                    } else if decibels >= ceiling {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                });
+            },
+            Normalized => {
+                values.iter_mut().for_each(|value| *value = to_decibels(*value));
+
+                let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+                let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+                // If the range is zero, values are scaled to 0.5 to avoid division by zero
+                if max - min < 1e-6 {
+                    values.iter_mut().for_each(|value| *value = 0.5);
+                } else {
+                    values.iter_mut().for_each(|value| *value = (*value - min) / (max - min));
+                }
+            },
+        }
+    }
+}
+
+fn to_decibels(magnitude: f32) -> f32 {
+    20.0 * (magnitude + DECIBEL_OFFSET).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaled(scale: SpectrumScale, magnitudes: &[f32]) -> Vec<f32> {
+        let mut values = magnitudes.to_vec();
+
+        scale.apply(&mut values);
+        values
+    }
+
+    fn assert_approx_eq(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        assert!(actual.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-4), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_linear_scales() {
+        let magnitudes = [0.0, 0.5, 2.0];
+
+        assert_approx_eq(&scaled(SpectrumScale::SqrtMagnitude, &magnitudes), &[0.0, 1.0, 2.0]);
+        assert_approx_eq(&scaled(SpectrumScale::Magnitude, &magnitudes), &magnitudes);
+        assert_approx_eq(&scaled(SpectrumScale::Power, &magnitudes), &[0.0, 0.25, 4.0]);
+    }
+
+    #[test]
+    fn test_fixed_decibel_range() {
+        let scale = SpectrumScale::from_name("decibels", [-80.0, 0.0]).unwrap();
+
+        // -inf dB, -80 dB, -40 dB, 0 dB and +20 dB
+        assert_approx_eq(&scaled(scale, &[0.0, 1e-4, 1e-2, 1.0, 10.0]), &[0.0, 0.0, 0.5, 1.0, 1.0]);
+
+        // Quiet spectra stay quiet, unlike with the normalized scale
+        assert_approx_eq(&scaled(scale, &[1e-4, 1e-3]), &[0.0, 0.25]);
+        assert_approx_eq(&scaled(SpectrumScale::Normalized, &[1e-4, 1e-3]), &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_normalized_silence() {
+        assert_approx_eq(&scaled(SpectrumScale::Normalized, &[0.0; 4]), &[0.5; 4]);
+        assert_approx_eq(&scaled(SpectrumScale::from_name("decibels", [-80.0, 0.0]).unwrap(), &[0.0; 4]), &[0.0; 4]);
+    }
+}
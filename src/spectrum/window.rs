@@ -0,0 +1,68 @@
+use ordered_float::OrderedFloat;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowFunction {
+    None,
+    Blackman,
+    Cosine {
+        a: OrderedFloat<f64>,
+        b: OrderedFloat<f64>,
+        c: OrderedFloat<f64>,
+        d: OrderedFloat<f64>,
+    },
+    Hamming,
+    Hanning,
+    Nuttall,
+    Triangular,
+}
+
+impl WindowFunction {
+    /// Names of the window functions as used in properties, along with their descriptions.
+    pub const ITEMS: &'static [(&'static str, &'static str)] = &[
+        ("none", "None (Rectangular)"),
+        ("blackman", "Blackman"),
+        ("cosine", "Cosine (Custom Coefficients)"),
+        ("hamming", "Hamming"),
+        ("hanning", "Hanning"),
+        ("nuttall", "Nuttall"),
+        ("triangular", "Triangular"),
+    ];
+
+    /// Returns the window function of the given name, the coefficients are only used by `cosine`.
+    pub fn from_name(name: &str, cosine_coefficients: [f64; 4]) -> Option<Self> {
+        use WindowFunction::*;
+
+        let [a, b, c, d] = cosine_coefficients;
+
+        Some(match name {
+            "none" => None,
+            "blackman" => Blackman,
+            "cosine" => Cosine {
+                a: OrderedFloat(a),
+                b: OrderedFloat(b),
+                c: OrderedFloat(c),
+                d: OrderedFloat(d),
+            },
+            "hamming" => Hamming,
+            "hanning" => Hanning,
+            "nuttall" => Nuttall,
+            "triangular" => Triangular,
+            _ => return Option::None,
+        })
+    }
+
+    pub fn generate_coefficients(self, len: usize) -> Vec<f32> {
+        use apodize::*;
+        use WindowFunction::*;
+
+        match self {
+            None => std::iter::repeat(1.0).take(len).collect::<Vec<_>>(),
+            Blackman => blackman_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
+            Cosine { a, b, c, d } => cosine_iter(*a, *b, *c, *d, len).map(|coef| coef as f32).collect::<Vec<_>>(),
+            Hamming => hamming_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
+            Hanning => hanning_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
+            Nuttall => nuttall_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
+            Triangular => triangular_iter(len).map(|coef| coef as f32).collect::<Vec<_>>(),
+        }
+    }
+}
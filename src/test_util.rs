@@ -0,0 +1,46 @@
+//! Test signals and helpers shared by the tests of the audio analysis.
+
+use crate::spectrum::{SpectrumAnalyzer, SpectrumAnalyzerParameters, WindowFunction};
+
+/// The sample rate of the test signals
+pub const SAMPLE_RATE: f64 = 48000.0;
+
+/// `len` samples of the sum of sine waves given as `(frequency, amplitude)`, sampled at `SAMPLE_RATE`.
+pub fn sines(components: &[(f64, f64)], len: usize) -> Vec<f32> {
+    (0..len).map(|index| {
+        let time = index as f64 / SAMPLE_RATE;
+
+        components.iter()
+            .map(|(frequency, amplitude)| amplitude * (2.0 * std::f64::consts::PI * frequency * time).sin())
+            .sum::<f64>() as f32
+    }).collect()
+}
+
+pub fn sine(frequency: f64, amplitude: f64, len: usize) -> Vec<f32> {
+    sines(&[(frequency, amplitude)], len)
+}
+
+/// Parameters of an analyzer using the Hanning window without zero padding.
+pub fn analyzer_parameters(fft_size: usize, hop_size: usize) -> SpectrumAnalyzerParameters {
+    SpectrumAnalyzerParameters {
+        window_function: WindowFunction::Hanning,
+        fft_size,
+        hop_size,
+        zero_padding: 1,
+    }
+}
+
+/// The magnitudes of the spectrum of the first `fft_size` samples.
+pub fn spectrum(samples: &[f32], fft_size: usize) -> Vec<f32> {
+    let mut analyzer = SpectrumAnalyzer::new(analyzer_parameters(fft_size, fft_size), SAMPLE_RATE);
+
+    analyzer.push_samples(&samples[..fft_size]);
+    assert!(analyzer.analyze());
+
+    analyzer.result().unwrap().magnitudes.to_vec()
+}
+
+/// The index of the greatest value.
+pub fn peak_index(values: &[f32]) -> usize {
+    (0..values.len()).max_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap()).unwrap()
+}
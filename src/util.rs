@@ -9,6 +9,8 @@ use obs_wrapper::obs_sys::{
 };
 use std::cmp::Ordering;
 use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 pub struct Indexed<T> {
     pub index: usize,
@@ -91,6 +93,74 @@ impl<T> Ord for Indexed<T> {
     }
 }
 
+/// Detects changes of files on disk by periodically polling their modification times.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    poll_interval: Duration,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item=PathBuf>, poll_interval: Duration) -> Self {
+        Self {
+            files: paths.into_iter()
+                .map(|path| {
+                    let modified = Self::modification_time(&path);
+                    (path, modified)
+                })
+                .collect(),
+            poll_interval,
+            last_poll: Instant::now(),
+        }
+    }
+
+    fn modification_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item=&Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Returns `true`, if any of the files was modified, created or removed since the last poll.
+    /// The files are only checked once the poll interval has elapsed.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < self.poll_interval {
+            return false;
+        }
+
+        self.last_poll = Instant::now();
+
+        let mut changed = false;
+
+        for (path, modified) in &mut self.files {
+            let current = Self::modification_time(path);
+
+            if *modified != current {
+                *modified = current;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// Converts a point in time to the date in the format of `builtin_date`, in the time zone of the point in time:
+/// `(year, month starting at 0, day of the month starting at 1, seconds since midnight)`
+pub fn date<Tz: chrono::TimeZone>(time: &chrono::DateTime<Tz>) -> [f32; 4] {
+    use chrono::{Datelike, Timelike};
+
+    let seconds = time.num_seconds_from_midnight() as f64 + time.nanosecond() as f64 * 1e-9;
+
+    [time.year() as f32, time.month0() as f32, time.day() as f32, seconds as f32]
+}
+
+/// The current date in the local time zone of the system, as Shadertoy's `iDate`.
+pub fn date_local() -> [f32; 4] {
+    date(&chrono::Local::now())
+}
+
 #[allow(non_camel_case_types)]
 pub type log_handler_t = ::std::option::Option<
     unsafe extern "C" fn(